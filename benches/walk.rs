@@ -0,0 +1,79 @@
+use checklfs::{check_tree_with, Check, CheckConfig, WalkStrategy};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use git2::{FileMode, Odb, Oid, Repository};
+
+/// Builds `Assets/Dir<n>/` with `files` assets and their metas in each of `dirs` directories,
+/// in a repository whose objects only live in memory.
+fn synthetic_repo(dirs: usize, files: usize) -> (Repository, Oid) {
+    let odb = Odb::new().unwrap();
+    odb.add_new_mempack_backend(1).unwrap();
+    let repo = Repository::from_odb(odb).unwrap();
+    let tree_id = write_tree(&repo, dirs, files);
+    (repo, tree_id)
+}
+
+fn write_tree(repo: &Repository, dirs: usize, files: usize) -> Oid {
+    let asset = repo.blob(&[0u8; 200]).unwrap();
+    let meta = repo
+        .blob(b"fileFormatVersion: 2\nguid: 0123456789abcdef\n")
+        .unwrap();
+
+    let mut assets = repo.treebuilder(None).unwrap();
+    for dir in 0..dirs {
+        let mut builder = repo.treebuilder(None).unwrap();
+        for file in 0..files {
+            let name = format!("Asset{}.png", file);
+            builder.insert(&name, asset, FileMode::Blob.into()).unwrap();
+            builder
+                .insert(format!("{}.meta", name), meta, FileMode::Blob.into())
+                .unwrap();
+        }
+        let name = format!("Dir{}", dir);
+        assets
+            .insert(&name, builder.write().unwrap(), FileMode::Tree.into())
+            .unwrap();
+        assets
+            .insert(format!("{}.meta", name), meta, FileMode::Blob.into())
+            .unwrap();
+    }
+
+    let mut root = repo.treebuilder(None).unwrap();
+    root.insert("Assets", assets.write().unwrap(), FileMode::Tree.into())
+        .unwrap();
+    root.write().unwrap()
+}
+
+/// Times checks that each walk the whole tree, every one reading the trees again against all of
+/// them replaying a single shared walk.
+fn bench_walk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("walk");
+    let config = CheckConfig::new().with_checks([
+        Check::MetaCase,
+        Check::ExtensionCase,
+        Check::DuplicateBasenames,
+        Check::EmptyFiles,
+        Check::ComponentLength,
+    ]);
+    for &(dirs, files) in &[(10, 100), (100, 100)] {
+        let (repo, tree_id) = synthetic_repo(dirs, files);
+        let entries = dirs * (files * 2 + 2);
+        for &(name, strategy) in &[
+            ("per-check", WalkStrategy::PerCheck),
+            ("shared", WalkStrategy::Shared),
+        ] {
+            group.bench_with_input(
+                BenchmarkId::new(name, entries),
+                &strategy,
+                |b, &strategy| {
+                    b.iter(|| {
+                        check_tree_with(&config, &repo, tree_id, None, strategy, |_| {}).unwrap()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_walk);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+//! `.gitattributes` read from a tree instead of the index or the working directory, so a commit
+//! is checked against the attributes it was committed with.
+
+use git2::{ObjectType, Repository, Tree, TreeWalkMode, TreeWalkResult};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// State of an attribute for a path, as in gitattributes(5).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttrValue {
+    /// `name`
+    Set,
+    /// `-name`
+    Unset,
+    /// `name=value`
+    Value(String),
+    /// Not mentioned, or reset with `!name`.
+    Unspecified,
+}
+
+/// A single pattern line of a `.gitattributes` file.
+#[derive(Debug)]
+pub struct AttrRule {
+    /// The `.gitattributes` file defining the rule.
+    pub source: PathBuf,
+    /// 1-based line number in `source`.
+    pub line: usize,
+    pub pattern: String,
+    pub attrs: Vec<(String, AttrValue)>,
+    dir: PathBuf,
+    regex: Regex,
+    /// Patterns without a slash match the file name at any depth below `dir`.
+    basename_only: bool,
+}
+
+impl AttrRule {
+    fn matches(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.dir) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        let subject = if self.basename_only {
+            relative.file_name().and_then(|name| name.to_str())
+        } else {
+            relative.to_str()
+        };
+        subject.is_some_and(|subject| self.regex.is_match(subject))
+    }
+
+    /// Value of the attribute if the rule mentions it, the last mention wins.
+    pub fn get(&self, name: &str) -> Option<&AttrValue> {
+        self.attrs
+            .iter()
+            .rev()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Translates a gitattributes glob to an anchored regex.
+fn glob_regex(glob: &str) -> Option<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if matches!(chars.peek(), Some('!') | Some('^')) {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).ok()
+}
+
+fn parse_attr(token: &str) -> (String, AttrValue) {
+    if let Some(name) = token.strip_prefix('-') {
+        (name.to_owned(), AttrValue::Unset)
+    } else if let Some(name) = token.strip_prefix('!') {
+        (name.to_owned(), AttrValue::Unspecified)
+    } else if let Some((name, value)) = token.split_once('=') {
+        (name.to_owned(), AttrValue::Value(value.to_owned()))
+    } else {
+        (token.to_owned(), AttrValue::Set)
+    }
+}
+
+/// Every attribute rule of a tree, ordered so that later rules take precedence.
+#[derive(Debug, Default)]
+pub struct Attributes {
+    rules: Vec<AttrRule>,
+}
+
+impl Attributes {
+    /// Reads the `.gitattributes` files of every directory of `tree`.
+    pub fn from_tree(repo: &Repository, tree: &Tree) -> Result<Attributes, git2::Error> {
+        Attributes::from_tree_at(repo, tree, Path::new(""))
+    }
+
+    /// Like [`Attributes::from_tree`] for a subtree at `root`, so that rules match the
+    /// repository-relative paths below it.
+    pub fn from_tree_at(
+        repo: &Repository,
+        tree: &Tree,
+        root: &Path,
+    ) -> Result<Attributes, git2::Error> {
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.name() == Some(".gitattributes") && entry.kind() == Some(ObjectType::Blob) {
+                files.push((root.join(dir), entry.id()));
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        let mut attributes = Attributes::default();
+        for (dir, id) in files {
+            let blob = repo.find_blob(id)?;
+            attributes.add(&dir, &String::from_utf8_lossy(blob.content()));
+        }
+        Ok(attributes)
+    }
+
+    /// Adds the rules of the `.gitattributes` file in `dir`, `""` for the root.
+    pub fn add(&mut self, dir: &Path, content: &str) {
+        for (index, line) in content.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            let pattern = match tokens.next() {
+                Some(pattern) if !pattern.starts_with('#') && !pattern.starts_with('!') => pattern,
+                _ => continue,
+            };
+            // `[attr]` macro definitions aren't supported, only the built-in `binary` macro
+            if pattern.starts_with("[attr]") {
+                continue;
+            }
+
+            let mut attrs = Vec::new();
+            for token in tokens {
+                let (name, value) = parse_attr(token);
+                if name == "binary" && value == AttrValue::Set {
+                    for name in &["diff", "merge", "text"] {
+                        attrs.push((name.to_string(), AttrValue::Unset));
+                    }
+                }
+                attrs.push((name, value));
+            }
+
+            let basename_only = !pattern.trim_end_matches('/').contains('/');
+            let glob = pattern.trim_start_matches('/');
+            let regex = match glob_regex(glob) {
+                Some(regex) => regex,
+                None => continue,
+            };
+            self.rules.push(AttrRule {
+                source: dir.join(".gitattributes"),
+                line: index + 1,
+                pattern: pattern.to_owned(),
+                attrs,
+                dir: dir.to_owned(),
+                regex,
+                basename_only,
+            });
+        }
+        // deeper files override their parents, the sort is stable so line order is kept
+        self.rules.sort_by_key(|rule| rule.dir.components().count());
+    }
+
+    pub fn rules(&self) -> &[AttrRule] {
+        &self.rules
+    }
+
+    /// Rules matching the repository-relative `path`, in precedence order.
+    pub fn matching<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a AttrRule> + 'a {
+        self.rules.iter().filter(move |rule| rule.matches(path))
+    }
+
+    /// Effective value of the attribute `name` for `path`.
+    pub fn get(&self, path: &Path, name: &str) -> AttrValue {
+        self.matching(path)
+            .filter_map(|rule| rule.get(name))
+            .last()
+            .cloned()
+            .unwrap_or(AttrValue::Unspecified)
+    }
+}
+
+/// Number of leading bytes git looks at to tell binary from text content.
+pub const BINARY_DETECTION_BYTES: usize = 8000;
+
+/// Whether the file is text, so text-only checks such as line ending or BOM checks apply to it.
+/// `-text` and `binary` mark files as binary and `text` as text, otherwise the content decides
+/// like git does: a NUL byte in the first [`BINARY_DETECTION_BYTES`] means binary.
+pub fn is_text(attributes: &Attributes, path: &Path, content: &[u8]) -> bool {
+    match attributes.get(path, "text") {
+        AttrValue::Set => true,
+        AttrValue::Unset => false,
+        AttrValue::Value(_) | AttrValue::Unspecified => {
+            !content.iter().take(BINARY_DETECTION_BYTES).any(|&b| b == 0)
+        }
+    }
+}
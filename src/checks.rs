@@ -0,0 +1,5333 @@
+//! The checks run against a tree. Each check reports its findings through an [`Emit`] callback
+//! as they are found, [`check_all_with`] runs the enabled checks in parallel and passes every
+//! finding to a single callback.
+
+use crate::attributes::{is_text, AttrValue, Attributes, BINARY_DETECTION_BYTES};
+use crate::locks;
+use anyhow::Result;
+use git2::*;
+use log::*;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Error of the public check functions, so library users can tell failures of git apart from
+/// unreadable project files without going through `anyhow`.
+#[derive(Debug)]
+pub enum CheckError {
+    /// A git operation failed, e.g. a missing object or an unreadable repository.
+    Git(git2::Error),
+    Io(std::io::Error),
+    /// A project file, option or pattern couldn't be parsed.
+    Parse(String),
+    Other(anyhow::Error),
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckError::Git(error) => write!(f, "git error: {}", error),
+            CheckError::Io(error) => write!(f, "io error: {}", error),
+            CheckError::Parse(message) => write!(f, "parse error: {}", message),
+            CheckError::Other(error) => write!(f, "{:#}", error),
+        }
+    }
+}
+
+impl std::error::Error for CheckError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckError::Git(error) => Some(error),
+            CheckError::Io(error) => Some(error),
+            CheckError::Parse(_) | CheckError::Other(_) => None,
+        }
+    }
+}
+
+impl From<git2::Error> for CheckError {
+    fn from(error: git2::Error) -> Self {
+        CheckError::Git(error)
+    }
+}
+
+impl From<std::io::Error> for CheckError {
+    fn from(error: std::io::Error) -> Self {
+        CheckError::Io(error)
+    }
+}
+
+impl From<ignore::Error> for CheckError {
+    fn from(error: ignore::Error) -> Self {
+        CheckError::Parse(error.to_string())
+    }
+}
+
+/// The checks use `anyhow` internally, errors are mapped back to their variant when they come
+/// straight from git, io or one of the parsers, or are a [`CheckError`] passed through a check
+/// thread.
+impl From<anyhow::Error> for CheckError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<CheckError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<git2::Error>() {
+            Ok(error) => return CheckError::Git(error),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<std::io::Error>() {
+            Ok(error) => return CheckError::Io(error),
+            Err(error) => error,
+        };
+        if error.is::<serde_json::Error>()
+            || error.is::<toml::de::Error>()
+            || error.is::<regex::Error>()
+            || error.is::<ignore::Error>()
+        {
+            return CheckError::Parse(error.to_string());
+        }
+        CheckError::Other(error)
+    }
+}
+
+/// Kind of check which produced a finding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Check {
+    Meta,
+    Lfs,
+    Case,
+    OsMetadata,
+    Importer,
+    Guid,
+    Manifest,
+    DuplicateBasenames,
+    Fbx,
+    Texture,
+    Audio,
+    Video,
+    AssetsStructure,
+    Scene,
+    Shader,
+    MetaLineEndings,
+    LfsInvalidOid,
+    MetaVersion,
+    LfsText,
+    LfsLocks,
+    Binary,
+    Gitignore,
+    EmptyFiles,
+    LfsAttributes,
+    MetaCase,
+    CommitMessage,
+    Resources,
+    StreamingAssets,
+    EditorFolderMeta,
+    Submodules,
+    UnityBinary,
+    ExtensionCase,
+    AsmdefReferences,
+    HistoryBlobs,
+    GuidStability,
+    MetaCommit,
+    ComponentLength,
+    StagedMeta,
+}
+
+impl Check {
+    pub const ALL: &'static [Check] = &[
+        Check::Meta,
+        Check::Lfs,
+        Check::Case,
+        Check::OsMetadata,
+        Check::Importer,
+        Check::Guid,
+        Check::Manifest,
+        Check::DuplicateBasenames,
+        Check::Fbx,
+        Check::Texture,
+        Check::Audio,
+        Check::Video,
+        Check::AssetsStructure,
+        Check::Scene,
+        Check::Shader,
+        Check::MetaLineEndings,
+        Check::LfsInvalidOid,
+        Check::MetaVersion,
+        Check::LfsText,
+        Check::LfsLocks,
+        Check::Binary,
+        Check::Gitignore,
+        Check::EmptyFiles,
+        Check::LfsAttributes,
+        Check::MetaCase,
+        Check::CommitMessage,
+        Check::Resources,
+        Check::StreamingAssets,
+        Check::EditorFolderMeta,
+        Check::Submodules,
+        Check::UnityBinary,
+        Check::ExtensionCase,
+        Check::AsmdefReferences,
+        Check::HistoryBlobs,
+        Check::GuidStability,
+        Check::MetaCommit,
+        Check::ComponentLength,
+        Check::StagedMeta,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::Meta => "meta",
+            Check::Lfs => "lfs",
+            Check::Case => "case",
+            Check::OsMetadata => "os-metadata",
+            Check::Importer => "importer",
+            Check::Guid => "guid",
+            Check::Manifest => "manifest",
+            Check::DuplicateBasenames => "duplicate-basenames",
+            Check::Fbx => "fbx",
+            Check::Texture => "texture",
+            Check::Audio => "audio",
+            Check::Video => "video",
+            Check::AssetsStructure => "assets-structure",
+            Check::Scene => "scene",
+            Check::Shader => "shader",
+            Check::MetaLineEndings => "meta-line-endings",
+            Check::LfsInvalidOid => "lfs-invalid-oid",
+            Check::MetaVersion => "meta-version",
+            Check::LfsText => "lfs-text",
+            Check::LfsLocks => "lfs-locks",
+            Check::Binary => "binary",
+            Check::Gitignore => "gitignore",
+            Check::EmptyFiles => "empty-files",
+            Check::LfsAttributes => "lfs-attributes",
+            Check::MetaCase => "meta-case",
+            Check::CommitMessage => "commit-message",
+            Check::Resources => "resources",
+            Check::StreamingAssets => "streaming-assets",
+            Check::EditorFolderMeta => "editor-folder-meta",
+            Check::Submodules => "submodules",
+            Check::UnityBinary => "unity-binary",
+            Check::ExtensionCase => "extension-case",
+            Check::AsmdefReferences => "asmdef-references",
+            Check::HistoryBlobs => "history-blobs",
+            Check::GuidStability => "guid-stability",
+            Check::MetaCommit => "meta-commit",
+            Check::ComponentLength => "component-length",
+            Check::StagedMeta => "staged-meta",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Check> {
+        Check::ALL
+            .iter()
+            .copied()
+            .find(|check| check.name() == name)
+    }
+
+    /// What the check reports, shown by `--list-checks`.
+    pub fn description(self) -> &'static str {
+        match self {
+            Check::Meta => {
+                "assets without a meta, stale metas and directories left with only metas"
+            }
+            Check::Lfs => "files tracked by filter=lfs that aren't committed as lfs pointers",
+            Check::Case => "paths colliding on case-insensitive filesystems",
+            Check::OsMetadata => "files created by the OS, e.g. .DS_Store or Thumbs.db",
+            Check::Importer => "metas whose importer doesn't match the asset's extension",
+            Check::Guid => "guids shared by several metas",
+            Check::Manifest => "invalid Packages/manifest.json",
+            Check::DuplicateBasenames => "assets sharing a file name across directories",
+            Check::Fbx => "large models outside of LFS",
+            Check::Texture => "large textures outside of LFS",
+            Check::Audio => "large audio clips outside of LFS",
+            Check::Video => "large videos outside of LFS",
+            Check::AssetsStructure => "assets outside the layout configured in .checklfs.toml",
+            Check::Scene => "large scenes and prefabs outside of LFS or not serialized as text",
+            Check::Shader => "binary or large shaders outside of LFS",
+            Check::MetaLineEndings => "metas with CRLF line endings",
+            Check::LfsInvalidOid => "lfs pointers with a malformed oid or size",
+            Check::MetaVersion => "metas with an unexpected fileFormatVersion",
+            Check::LfsText => "source and text files committed as lfs pointers",
+            Check::LfsLocks => "files locked on the lfs server by another user",
+            Check::Binary => "binary content outside of LFS",
+            Check::Gitignore => "committed paths matching .gitignore",
+            Check::EmptyFiles => "zero-byte files, usually left by a failed export",
+            Check::LfsAttributes => ".gitattributes lfs rules missing -text",
+            Check::MetaCase => "assets and metas colliding on case-insensitive filesystems",
+            Check::CommitMessage => "commit messages not matching --commit-message-pattern",
+            Check::Resources => "large assets in Resources directories",
+            Check::StreamingAssets => "large StreamingAssets files outside of LFS",
+            Check::EditorFolderMeta => "metas of Editor folders that aren't folder metas",
+            Check::Submodules => "relative, local or unencrypted urls in .gitmodules",
+            Check::UnityBinary => "binary serialized Unity assets outside of LFS",
+            Check::ExtensionCase => "extensions spelled with a different case than most files",
+            Check::AsmdefReferences => "asmdef GUID references to assemblies missing from the tree",
+            Check::HistoryBlobs => {
+                "blobs above a size threshold added anywhere in the history, with --deep"
+            }
+            Check::GuidStability => "metas whose guid changed since the parent commit, or --since",
+            Check::MetaCommit => {
+                "assets and metas first committed in different commits since --since"
+            }
+            Check::ComponentLength => {
+                "file or directory names longer than --max-component-length bytes"
+            }
+            Check::StagedMeta => {
+                "assets staged without their meta or metas without their asset, with --staged"
+            }
+        }
+    }
+
+    /// How findings of the check are usually fixed, shown with `--report-format long`.
+    pub fn suggestion(self) -> &'static str {
+        match self {
+            Check::Meta => "commit the meta Unity generates, or delete the stale meta",
+            Check::Lfs => "track the file with filter=lfs and run `git add --renormalize`",
+            Check::Case => "rename one of the paths so they differ by more than case",
+            Check::OsMetadata => "untrack the file with `git rm --cached` and add it to .gitignore",
+            Check::Importer => "reimport the asset so Unity writes the expected importer",
+            Check::Guid => "delete one of the metas and reimport the asset to get a new guid",
+            Check::Manifest => "fix Packages/manifest.json so the package manager can resolve it",
+            Check::DuplicateBasenames => "rename the assets so lookups by name are unambiguous",
+            Check::Fbx
+            | Check::Texture
+            | Check::Audio
+            | Check::Video
+            | Check::Scene
+            | Check::Shader
+            | Check::Binary
+            | Check::StreamingAssets => "add the extension to .gitattributes with filter=lfs",
+            Check::AssetsStructure => "move the assets to match the layout in .checklfs.toml",
+            Check::MetaLineEndings => "normalize metas to LF, e.g. with `*.meta text eol=lf`",
+            Check::LfsInvalidOid => "re-add the file with git-lfs installed to rewrite the pointer",
+            Check::MetaVersion => "reimport the asset with the project's Unity version",
+            Check::LfsText => "remove filter=lfs for the extension and run `git add --renormalize`",
+            Check::LfsLocks => "ask the lock owner to release the lock, or `git lfs unlock` it",
+            Check::Gitignore => "untrack the path with `git rm --cached` or adjust .gitignore",
+            Check::EmptyFiles => "re-export or re-save the file, or delete it if unused",
+            Check::LfsAttributes => "append -text to the pattern, as `git lfs track` writes it",
+            Check::MetaCase => "rename the asset and its meta together so both use one spelling",
+            Check::CommitMessage => {
+                "reword the commit with `git commit --amend` to match the pattern"
+            }
+            Check::Resources => "move the asset out of Resources and load it with Addressables",
+            Check::EditorFolderMeta => "delete the meta and let Unity regenerate it for the folder",
+            Check::Submodules => "use an absolute https or ssh url in .gitmodules",
+            Check::UnityBinary => {
+                "track the extension with filter=lfs, or set Asset Serialization to Force Text"
+            }
+            Check::ExtensionCase => "rename the files to the dominant extension casing",
+            Check::AsmdefReferences => {
+                "fix the reference in the asmdef, or commit the referenced assembly and its meta"
+            }
+            Check::HistoryBlobs => "rewrite the history, e.g. with `git lfs migrate import`, to drop the blob or move it to lfs",
+            Check::GuidStability => "restore the previous guid of the meta, regenerated metas break every reference to the asset",
+            Check::MetaCommit => "commit assets with their metas, e.g. squash the commits before merging",
+            Check::ComponentLength => "shorten the file or directory name",
+            Check::StagedMeta => "stage the asset together with its meta, e.g. `git add Assets/Foo.png Assets/Foo.png.meta`",
+        }
+    }
+
+    /// Whether the check runs when no `--check` is given.
+    pub fn default_enabled(self) -> bool {
+        match self {
+            Check::Meta
+            | Check::Lfs
+            | Check::LfsInvalidOid
+            | Check::Case
+            | Check::OsMetadata
+            | Check::Manifest
+            | Check::StagedMeta => true,
+            Check::Importer
+            | Check::Guid
+            | Check::DuplicateBasenames
+            | Check::Fbx
+            | Check::Texture
+            | Check::Audio
+            | Check::Video
+            | Check::AssetsStructure
+            | Check::Scene
+            | Check::Shader
+            | Check::MetaLineEndings
+            | Check::MetaVersion
+            | Check::LfsText
+            | Check::LfsLocks
+            | Check::Binary
+            | Check::Gitignore
+            | Check::EmptyFiles
+            | Check::LfsAttributes
+            | Check::MetaCase
+            | Check::CommitMessage
+            | Check::Resources
+            | Check::StreamingAssets
+            | Check::EditorFolderMeta
+            | Check::Submodules
+            | Check::UnityBinary
+            | Check::ExtensionCase
+            | Check::AsmdefReferences
+            | Check::HistoryBlobs
+            | Check::GuidStability
+            | Check::MetaCommit
+            | Check::ComponentLength => false,
+        }
+    }
+
+    /// Exit code bit of the check's category, OR-combined when several checks fail. `1` is
+    /// reserved for internal errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Check::Meta
+            | Check::Importer
+            | Check::Guid
+            | Check::MetaLineEndings
+            | Check::MetaVersion
+            | Check::MetaCase
+            | Check::EditorFolderMeta
+            | Check::GuidStability
+            | Check::MetaCommit
+            | Check::StagedMeta => 2,
+            Check::Case
+            | Check::DuplicateBasenames
+            | Check::ExtensionCase
+            | Check::ComponentLength => 4,
+            Check::Lfs
+            | Check::Fbx
+            | Check::Texture
+            | Check::Audio
+            | Check::Video
+            | Check::Scene
+            | Check::Shader
+            | Check::LfsInvalidOid
+            | Check::LfsText
+            | Check::LfsLocks
+            | Check::Binary
+            | Check::LfsAttributes
+            | Check::StreamingAssets
+            | Check::UnityBinary => 8,
+            Check::OsMetadata | Check::Gitignore | Check::EmptyFiles | Check::HistoryBlobs => 16,
+            Check::Manifest
+            | Check::AssetsStructure
+            | Check::Resources
+            | Check::Submodules
+            | Check::AsmdefReferences => 32,
+            Check::CommitMessage => 64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Severity::Error),
+            "warning" => Ok(Severity::Warning),
+            _ => Err(format!("unknown severity: {}", s)),
+        }
+    }
+}
+
+/// How much of a finding is shown to humans, independent of the output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Just the path, e.g. to pipe into other tools.
+    PathOnly,
+    /// The check, path and message.
+    #[default]
+    Short,
+    /// [`ReportFormat::Short`] followed by how to fix the finding.
+    Long,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "path-only" => Ok(ReportFormat::PathOnly),
+            "short" => Ok(ReportFormat::Short),
+            "long" => Ok(ReportFormat::Long),
+            _ => Err(format!("unknown report format: {}", s)),
+        }
+    }
+}
+
+/// A single violation reported by a check.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn error<P: Into<PathBuf>>(check: Check, path: P, message: String) -> Self {
+        Finding {
+            check,
+            severity: Severity::Error,
+            path: path.into(),
+            message,
+        }
+    }
+
+    pub fn warning<P: Into<PathBuf>>(check: Check, path: P, message: String) -> Self {
+        Finding {
+            severity: Severity::Warning,
+            ..Finding::error(check, path, message)
+        }
+    }
+
+    /// The finding as a single line of text.
+    pub fn report(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::PathOnly => self.path.display().to_string(),
+            ReportFormat::Short => format!(
+                "[{}] {}: {}",
+                self.check.name(),
+                self.path.display(),
+                self.message
+            ),
+            ReportFormat::Long => format!(
+                "{}, fix: {}",
+                self.report(ReportFormat::Short),
+                self.check.suggestion()
+            ),
+        }
+    }
+
+    pub fn log(&self, format: ReportFormat) {
+        let level = match self.severity {
+            Severity::Error => Level::Error,
+            Severity::Warning => Level::Warn,
+        };
+        log!(level, "{}", self.report(format));
+    }
+}
+
+/// Paths the checks report on: the paths changed by a commit, used to limit reporting to what
+/// the commit touched, minus the paths excluded with [`CheckConfig::exclude`].
+#[derive(Clone, Debug, Default)]
+pub struct PathFilter {
+    /// `None` for every path of the tree.
+    paths: Option<HashSet<PathBuf>>,
+    exclude: Option<ignore::gitignore::Gitignore>,
+}
+
+impl PathFilter {
+    /// Limits reporting to `paths`.
+    pub fn paths(paths: HashSet<PathBuf>) -> Self {
+        PathFilter {
+            paths: Some(paths),
+            exclude: None,
+        }
+    }
+
+    /// Metas are excluded with their asset.
+    fn excludes(&self, path: &Path, is_dir: bool) -> bool {
+        let exclude = match &self.exclude {
+            Some(exclude) => exclude,
+            None => return false,
+        };
+        let asset = path.to_str().and_then(|path| path.strip_suffix(".meta"));
+        exclude
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+            || asset
+                .is_some_and(|asset| exclude.matched_path_or_any_parents(asset, true).is_ignore())
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        // reported paths can be directories, e.g. a directory without meta
+        !self.excludes(path, true) && self.paths.as_ref().is_none_or(|paths| paths.contains(path))
+    }
+
+    /// Whether the directory `dir` holds paths of the filter.
+    pub fn descends(&self, dir: &Path) -> bool {
+        !self.excludes(dir, true)
+            && self
+                .paths
+                .as_ref()
+                .is_none_or(|paths| paths.iter().any(|path| path.starts_with(dir)))
+    }
+}
+
+/// Receives the findings of a check as they are found.
+pub type Emit<'a> = dyn FnMut(Finding) + 'a;
+
+fn filter_contains(filter: Option<&PathFilter>, path: &Path) -> bool {
+    filter.is_none_or(|filter| filter.contains(path))
+}
+
+/// Whether the directory `dir` holds filtered paths, walks skip the subtrees that don't since
+/// nothing in them is reported.
+fn filter_descends(filter: Option<&PathFilter>, dir: &Path) -> bool {
+    filter.is_none_or(|filter| filter.descends(dir))
+}
+
+/// Whether the path is excluded. Walks comparing paths across the whole tree can't skip
+/// unchanged subtrees but still skip excluded ones.
+fn filter_excludes(filter: Option<&PathFilter>, path: &Path, is_dir: bool) -> bool {
+    filter.is_some_and(|filter| filter.excludes(path, is_dir))
+}
+
+bitflags::bitflags! {
+    /// What was found in the tree for a path: the asset itself, its meta, or both.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    struct MetaStatus: u8 {
+        const FILE = 0b001;
+        const META = 0b010;
+    }
+}
+
+impl MetaStatus {
+    fn has_file(self) -> bool {
+        self.contains(MetaStatus::FILE)
+    }
+
+    fn has_meta(self) -> bool {
+        self.contains(MetaStatus::META)
+    }
+}
+
+/// Whether Unity3d ignores the file name, and so never creates a meta for it. Unity skips hidden
+/// files and directories, starting with `.`, and those ending with `~`, which is how packages
+/// ship `Documentation~` and `Samples~` without importing them. This is the default ignore rule
+/// of [`test_meta`] and [`CheckConfig::meta_ignore`].
+pub fn test_meta_ignore(name: &str) -> bool {
+    name.starts_with('.') || name.ends_with('~')
+}
+
+/// Opens the repository at `path` with the extra `object_dirs` as alternates, like git does with
+/// `GIT_ALTERNATE_OBJECT_DIRECTORIES`.
+pub fn open_repository(path: &Path, object_dirs: &[PathBuf]) -> Result<Repository, git2::Error> {
+    let repo = Repository::open(path)?;
+    if !object_dirs.is_empty() {
+        let odb = repo.odb()?;
+        for dir in object_dirs {
+            odb.add_disk_alternate(&dir.to_string_lossy())?;
+        }
+    }
+    Ok(repo)
+}
+
+/// Submodules are gitlink entries pointing to a commit of another repository, which isn't
+/// available here, so the checks skip them.
+pub fn is_submodule(entry: &TreeEntry) -> bool {
+    entry.kind() == Some(ObjectType::Commit)
+}
+
+/// Why a submodule url isn't safe to clone, `None` for absolute https and ssh urls.
+fn submodule_url_problem(url: &str) -> Option<&'static str> {
+    if url.starts_with("https://") || url.starts_with("ssh://") {
+        return None;
+    }
+    if url.starts_with("./") || url.starts_with("../") {
+        return Some("relative url, resolved against wherever the superproject was cloned from");
+    }
+    if url.starts_with("file://") || url.starts_with('/') || url.starts_with('\\') {
+        return Some("local filesystem path");
+    }
+    if url.starts_with("http://") || url.starts_with("git://") {
+        return Some("unencrypted transport");
+    }
+    if url.contains("://") {
+        return Some("unsupported transport");
+    }
+    // scp-like `git@host:path` is ssh, as opposed to a drive letter like `C:\`
+    match url.split_once(':') {
+        Some((host, _)) if host.len() > 1 && !host.contains('/') => None,
+        _ => Some("local filesystem path"),
+    }
+}
+
+/// Reports submodules of `.gitmodules` whose url isn't an absolute https or ssh url. Relative
+/// urls resolve against the superproject's remote, so a fork or mirror silently points them to
+/// other repositories, and local paths only exist on the machine that added the submodule.
+fn test_gitmodules_safety(
+    _config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking submodule urls");
+
+    let path = root.join(".gitmodules");
+    let entry = match tree.get_name(".gitmodules") {
+        Some(entry) if entry.kind() == Some(ObjectType::Blob) => entry,
+        _ => return Ok(()),
+    };
+    if !filter_contains(filter, &path) {
+        return Ok(());
+    }
+
+    // git2 only parses config files, so the blob goes through a temporary one
+    let blob = repo.find_blob(entry.id())?;
+    let mut file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut file, blob.content())?;
+    let config = match git2::Config::open(file.path()) {
+        Ok(config) => config,
+        Err(e) => {
+            emit(Finding::error(
+                Check::Submodules,
+                path,
+                format!("can't parse .gitmodules: {}", e.message()),
+            ));
+            return Ok(());
+        }
+    };
+
+    let mut urls = Vec::new();
+    config
+        .entries(Some(r"submodule\..*\.url"))?
+        .for_each(|entry| {
+            if let (Some(name), Some(url)) = (entry.name(), entry.value()) {
+                urls.push((name.to_owned(), url.to_owned()));
+            }
+        })?;
+
+    for (name, url) in urls {
+        let submodule = name
+            .strip_prefix("submodule.")
+            .and_then(|name| name.strip_suffix(".url"))
+            .unwrap_or(&name);
+        if let Some(problem) = submodule_url_problem(&url) {
+            emit(Finding::error(
+                Check::Submodules,
+                &path,
+                format!("submodule {:?} url {:?}: {}", submodule, url, problem),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Directories in which every entry needs a meta, `Assets` unless configured otherwise. `.`
+/// makes the whole tree an asset root, for projects keeping assets at the repository root.
+#[derive(Debug, Clone)]
+pub struct AssetRoots {
+    roots: Vec<PathBuf>,
+    /// Whether the roots are the default ones, replaced by the first [`AssetRoots::push`].
+    is_default: bool,
+}
+
+impl AssetRoots {
+    pub fn new<I, S>(roots: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let roots = roots
+            .into_iter()
+            .map(|root| asset_root_path(root.as_ref()))
+            .collect();
+        AssetRoots {
+            roots,
+            is_default: false,
+        }
+    }
+
+    /// Adds a root, the first one replaces the default `Assets`.
+    pub fn push(&mut self, root: &str) {
+        if self.is_default {
+            self.roots.clear();
+            self.is_default = false;
+        }
+        self.roots.push(asset_root_path(root));
+    }
+
+    /// Whether the path is inside one of the roots. The roots themselves don't need a meta.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots
+            .iter()
+            .any(|root| path.starts_with(root) && path != root.as_path())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Path> {
+        self.roots.iter().map(PathBuf::as_path)
+    }
+}
+
+impl Default for AssetRoots {
+    fn default() -> Self {
+        AssetRoots {
+            is_default: true,
+            ..AssetRoots::new(["Assets"])
+        }
+    }
+}
+
+fn asset_root_path(root: &str) -> PathBuf {
+    let root = root.trim_end_matches('/');
+    let root = root.strip_prefix("./").unwrap_or(root);
+    // `.` is stored as the empty path, which every tree path starts with
+    match root {
+        "." | "" => PathBuf::new(),
+        root => PathBuf::from(root),
+    }
+}
+
+/// Reports assets under the asset roots without a meta, metas without an asset, empty
+/// directories and directories left with only metas. `ignore_fn` tells whether a file name is
+/// ignored by the engine, so it needs no meta and any meta for it is stale, [`test_meta_ignore`]
+/// for Unity. With `respect_gitignore` paths ignored by the `.gitignore` files of the tree are
+/// skipped too, as Unity doesn't import them.
+pub fn test_meta(
+    config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    let asset_roots = &config.asset_roots;
+    let ignore_fn = &*config.meta_ignore;
+    let respect_gitignore = config.respect_gitignore;
+    info!("checking meta files");
+
+    let gitignores = if respect_gitignore {
+        Some(TreeGitignores::from_tree(repo, tree, root)?)
+    } else {
+        None
+    };
+
+    let mut name_set = HashMap::new();
+    let mut empty_dirs = Vec::new();
+    iter_tree_meta(
+        repo,
+        root,
+        tree,
+        &mut name_set,
+        &mut empty_dirs,
+        filter,
+        asset_roots,
+        &ignore_fn,
+        gitignores.as_ref(),
+        emit,
+    )?;
+    test_empty_directories(&mut name_set, &empty_dirs, filter, asset_roots, emit);
+
+    for (path, status) in name_set {
+        if !asset_roots.contains(&path) {
+            trace!(
+                "[meta] {}: skip, outside of the asset roots",
+                path.display()
+            );
+            continue;
+        }
+
+        // a file is affected when either itself or its meta was changed
+        let mut meta_path = path.clone().into_os_string();
+        meta_path.push(".meta");
+        if !filter_contains(filter, &path) && !filter_contains(filter, Path::new(&meta_path)) {
+            trace!("[meta] {}: skip, unchanged", path.display());
+            continue;
+        }
+
+        trace!("[meta] {}: {:?}", path.display(), status);
+        if !status.has_meta() {
+            emit(Finding::error(
+                Check::Meta,
+                path,
+                "missing meta file".into(),
+            ));
+        } else if !status.has_file() && is_folder_meta(repo, tree, root, Path::new(&meta_path)) {
+            emit(Finding::error(
+                Check::Meta,
+                path,
+                "orphan folder meta, the folder was deleted or holds no tracked files".into(),
+            ));
+        } else if !status.has_file() {
+            emit(Finding::error(
+                Check::Meta,
+                path,
+                "meta file without asset".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The blob at `path`, a path of the tree checked at `root`.
+fn find_blob_at<'r>(
+    repo: &'r Repository,
+    tree: &Tree,
+    root: &Path,
+    path: &Path,
+) -> Option<Blob<'r>> {
+    let entry = tree.get_path(path.strip_prefix(root).ok()?).ok()?;
+    repo.find_blob(entry.id()).ok()
+}
+
+/// Unity marks the metas of folders with `folderAsset: yes`.
+fn has_folder_asset(content: &[u8]) -> bool {
+    String::from_utf8_lossy(content)
+        .lines()
+        .any(|line| line.trim() == "folderAsset: yes")
+}
+
+/// Whether the meta at `meta_path`, a path of the tree checked at `root`, describes a folder.
+fn is_folder_meta(repo: &Repository, tree: &Tree, root: &Path, meta_path: &Path) -> bool {
+    find_blob_at(repo, tree, root, meta_path).is_some_and(|blob| has_folder_asset(blob.content()))
+}
+
+/// Reports directories in the `Editor` folder of an asset root, including the folder itself,
+/// whose meta doesn't mark them as a folder with `folderAsset: yes`. Unity recreates `Editor`
+/// folders on its own, which leaves metas of deleted files behind for them. Missing metas are
+/// reported by the meta check.
+fn test_editor_folder_meta(
+    config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    let asset_roots = &config.asset_roots;
+    info!("checking metas of Editor folders");
+
+    let folders = asset_roots
+        .iter()
+        .map(|asset_root| asset_root.join("Editor"))
+        .collect::<Vec<_>>();
+    let mut dirs = Vec::new();
+    ctx.walk(|dir, entry| {
+        if entry.kind() != Some(ObjectType::Tree) || entry.is_submodule() {
+            return TreeWalkResult::Ok;
+        }
+        let path = root.join(dir).join(entry.name().unwrap_or(""));
+        let on_the_way = folders
+            .iter()
+            .any(|folder| path.starts_with(folder) || folder.starts_with(&path));
+        if !on_the_way || !filter_descends(filter, &path) {
+            return TreeWalkResult::Skip;
+        }
+        if folders.iter().any(|folder| path.starts_with(folder)) {
+            dirs.push(path);
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for dir in dirs {
+        let mut meta_path = dir.clone().into_os_string();
+        meta_path.push(".meta");
+        let meta_path = PathBuf::from(meta_path);
+        if !filter_contains(filter, &dir) && !filter_contains(filter, &meta_path) {
+            continue;
+        }
+        let blob = match find_blob_at(repo, tree, root, &meta_path) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if !has_folder_asset(blob.content()) {
+            emit(Finding::error(
+                Check::EditorFolderMeta,
+                meta_path,
+                "folder meta without `folderAsset: yes`, likely left from a deleted file".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reports empty tree entries under the asset roots. These are usually left behind after deleting
+/// assets, and are removed from `names` so the meta check doesn't report them again.
+fn test_empty_directories(
+    names: &mut HashMap<PathBuf, MetaStatus>,
+    empty_dirs: &[PathBuf],
+    filter: Option<&PathFilter>,
+    asset_roots: &AssetRoots,
+    emit: &mut Emit,
+) {
+    for path in empty_dirs {
+        names.remove(path);
+        if asset_roots.contains(path) && filter_contains(filter, path) {
+            emit(Finding::error(Check::Meta, path, "empty directory".into()));
+        }
+    }
+}
+
+/// Whether every entry Unity doesn't ignore is a `.meta` blob, i.e. only metadata survived the
+/// deletion of the directory's assets.
+fn contains_only_meta(tree: &Tree, ignore_fn: &dyn Fn(&str) -> bool) -> bool {
+    let mut entries = tree
+        .iter()
+        .filter(|entry| entry.name().is_some_and(|name| !ignore_fn(name)))
+        .peekable();
+    entries.peek().is_some()
+        && entries.all(|entry| {
+            entry.kind() == Some(ObjectType::Blob)
+                && entry.name().is_some_and(|name| name.ends_with(".meta"))
+        })
+}
+
+/// Base name of an entry that is a meta or a copy of one, e.g. `Foo.png` for `Foo.png.meta` as
+/// well as for `Foo.png.META` or `Foo.png.meta.orig` left by a merge tool.
+fn meta_base_name(name: &str) -> Option<&str> {
+    let index = name.to_ascii_lowercase().rfind(".meta")?;
+    let (base, rest) = (&name[..index], &name[index + ".meta".len()..]);
+    (!base.is_empty() && (rest.is_empty() || rest.starts_with('.'))).then_some(base)
+}
+
+/// Blobs of the directory that collapse to the same asset as another meta, mapped to the meta
+/// kept for the asset: `<asset>.meta` if present, else the first in tree order.
+fn duplicate_metas(tree: &Tree) -> HashMap<String, String> {
+    let mut by_base: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in tree.iter() {
+        if entry.kind() != Some(ObjectType::Blob) {
+            continue;
+        }
+        if let Some(name) = entry.name() {
+            if let Some(base) = meta_base_name(name) {
+                by_base
+                    .entry(base.to_owned())
+                    .or_default()
+                    .push(name.to_owned());
+            }
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for (base, mut names) in by_base.into_iter().filter(|(_, names)| names.len() > 1) {
+        let canonical = format!("{}.meta", base);
+        let kept = names
+            .iter()
+            .position(|name| *name == canonical)
+            .unwrap_or(0);
+        let kept = names.remove(kept);
+        for name in names {
+            duplicates.insert(name, kept.clone());
+        }
+    }
+    duplicates
+}
+
+#[allow(clippy::too_many_arguments)]
+fn iter_tree_meta(
+    repo: &Repository,
+    prefix: &Path,
+    tree: &Tree,
+    names: &mut HashMap<PathBuf, MetaStatus>,
+    empty_dirs: &mut Vec<PathBuf>,
+    filter: Option<&PathFilter>,
+    asset_roots: &AssetRoots,
+    ignore_fn: &dyn Fn(&str) -> bool,
+    gitignores: Option<&TreeGitignores>,
+    emit: &mut Emit,
+) -> Result<()> {
+    let duplicates = duplicate_metas(tree);
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            None => continue,
+            Some(name) => name,
+        };
+
+        // a copy of a meta is neither an asset nor the asset's meta
+        if let Some(meta) = duplicates.get(name) {
+            let path = prefix.join(name);
+            if asset_roots.contains(&path) && filter_contains(filter, &path) {
+                emit(Finding::error(
+                    Check::Meta,
+                    path,
+                    format!("duplicate meta, {} is already the meta of the asset", meta),
+                ));
+            }
+            continue;
+        }
+
+        // a meta for an ignored file is always stale, e.g. `foo~.meta`
+        if let Some(base_name) = name.strip_suffix(".meta") {
+            let path = prefix.join(name);
+            if ignore_fn(base_name)
+                && entry.kind() == Some(ObjectType::Blob)
+                && asset_roots.contains(&path)
+                && filter_contains(filter, &path)
+            {
+                emit(Finding::error(
+                    Check::Meta,
+                    path,
+                    format!("meta file for ignored asset {:?}", base_name),
+                ));
+                continue;
+            }
+        }
+
+        if ignore_fn(name) {
+            trace!(
+                "[meta] {}: skip, ignored by unity",
+                prefix.join(name).display()
+            );
+            continue;
+        }
+
+        if let Some(glob) = gitignores.and_then(|gitignores| {
+            let is_dir = entry.kind() == Some(ObjectType::Tree);
+            gitignores.matched(&prefix.join(name), is_dir)
+        }) {
+            trace!(
+                "[meta] {}: skip, ignored by {:?} of {}",
+                prefix.join(name).display(),
+                glob.original(),
+                glob.from()
+                    .map_or(".gitignore".into(), |from| from.display().to_string())
+            );
+            continue;
+        }
+
+        // neither the submodule nor its own meta are checked
+        if is_submodule(&entry) {
+            let status = names.entry(prefix.join(name)).or_default();
+            status.insert(MetaStatus::FILE | MetaStatus::META);
+            continue;
+        }
+
+        // entries are classified by their kind rather than their name, directories may have
+        // dots in their name and blobs don't need to be read at all
+        let path = prefix.join(name);
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                let tree = repo.find_tree(entry.id())?;
+                if tree.is_empty() {
+                    empty_dirs.push(path.clone());
+                } else if contains_only_meta(&tree, ignore_fn)
+                    && asset_roots.contains(&path)
+                    && filter_contains(filter, &path)
+                {
+                    emit(Finding::error(
+                        Check::Meta,
+                        &path,
+                        "directory contains only meta files, its assets were deleted".into(),
+                    ));
+                }
+
+                if filter_descends(filter, &path) {
+                    iter_tree_meta(
+                        repo,
+                        &path,
+                        &tree,
+                        names,
+                        empty_dirs,
+                        filter,
+                        asset_roots,
+                        ignore_fn,
+                        gitignores,
+                        emit,
+                    )?;
+                }
+
+                names.entry(path).or_default().insert(MetaStatus::FILE);
+            }
+            Some(ObjectType::Blob) => match name.strip_suffix(".meta") {
+                Some(base_name) if !base_name.is_empty() => {
+                    names
+                        .entry(prefix.join(base_name))
+                        .or_default()
+                        .insert(MetaStatus::META);
+                }
+                _ => names.entry(path).or_default().insert(MetaStatus::FILE),
+            },
+            _ => {
+                continue;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Which paths are compared against each other by the case check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseScope {
+    /// Entries of the same directory, which is what breaks a checkout. Colliding directories are
+    /// reported once instead of once for every file below them.
+    Dir,
+    /// Full lowercased paths across the whole tree.
+    Global,
+}
+
+impl std::str::FromStr for CaseScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "dir" => Ok(CaseScope::Dir),
+            "global" => Ok(CaseScope::Global),
+            _ => Err(format!("unknown case scope: {}", s)),
+        }
+    }
+}
+
+fn test_case(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    let scope = config.case_scope;
+    info!("checking case-insensitive-duplicated files");
+
+    let mut name_set = HashMap::new();
+    iter_tree_case(repo, root, tree, &mut name_set, filter, scope, emit)?;
+    Ok(())
+}
+
+/// `names` maps each case-insensitive key to the first path seen with it. The pre-order walk
+/// visits entries in git's tree order, so the first path is deterministic and is treated as the
+/// canonical one, later paths are suggested for renaming.
+fn iter_tree_case(
+    repo: &Repository,
+    prefix: &Path,
+    tree: &Tree,
+    names: &mut HashMap<String, PathBuf>,
+    filter: Option<&PathFilter>,
+    scope: CaseScope,
+    emit: &mut Emit,
+) -> Result<()> {
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            None => continue,
+            Some(name) => name,
+        };
+        if is_submodule(&entry) {
+            continue;
+        }
+        let obj = entry.to_object(repo)?;
+        let lower_name = name.to_lowercase();
+        let name = prefix.join(name);
+        if filter_excludes(filter, &name, obj.kind() == Some(ObjectType::Tree)) {
+            continue;
+        }
+
+        let path_str = name.to_str().expect("non-utf8 filename");
+        let key = match scope {
+            CaseScope::Global => path_str.to_lowercase(),
+            // the parent keeps its case so that only siblings are compared
+            CaseScope::Dir => {
+                let prefix_str = prefix.to_str().expect("non-utf8 filename");
+                format!("{}/{}", prefix_str, lower_name)
+            }
+        };
+
+        match names.entry(key) {
+            Entry::Vacant(vacant) => {
+                trace!("[case] {}: ok", name.display());
+                vacant.insert(name.clone());
+            }
+            Entry::Occupied(canonical) => {
+                trace!("[case] {}: duplicated", name.display());
+                if filter_contains(filter, &name) {
+                    emit(Finding::error(
+                        Check::Case,
+                        &name,
+                        format!(
+                            "case-insensitive duplicated entry, rename it or merge it into {}",
+                            canonical.get().display()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(ObjectType::Tree) = obj.kind() {
+            let tree = obj.peel_to_tree()?;
+            iter_tree_case(repo, &name, &tree, names, filter, scope, emit)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports assets under the asset roots whose paths, including those only known from a meta,
+/// collide on a case-insensitive filesystem, together with which of them have a file and a meta.
+/// `Foo.png` with `foo.png.meta` looks like a missing meta to git but pairs up on Windows, so
+/// renaming only one side leaves the project broken; the case and meta checks each see half.
+fn test_meta_case(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { root, filter, .. } = *ctx;
+    let asset_roots = &config.asset_roots;
+    let ignore_fn = &*config.meta_ignore;
+    info!("checking case collisions of assets and metas");
+
+    // lowercased asset path -> status of every spelling of it
+    let mut spellings: HashMap<String, BTreeMap<PathBuf, MetaStatus>> = HashMap::new();
+    ctx.walk(|dir, entry| {
+        let name = match entry.name() {
+            Some(name) if !entry.is_submodule() && !ignore_fn(name) => name,
+            _ => return TreeWalkResult::Skip,
+        };
+        let is_dir = entry.kind() == Some(ObjectType::Tree);
+        if filter_excludes(filter, &root.join(dir).join(name), is_dir) {
+            return TreeWalkResult::Skip;
+        }
+        let (base_name, status) = match name.strip_suffix(".meta") {
+            Some(base_name) if entry.kind() == Some(ObjectType::Blob) => {
+                (base_name, MetaStatus::META)
+            }
+            _ => (name, MetaStatus::FILE),
+        };
+        let path = root.join(dir).join(base_name);
+        if asset_roots.contains(&path) {
+            let key = path.to_string_lossy().to_lowercase();
+            let spelling = spellings.entry(key).or_default().entry(path).or_default();
+            spelling.insert(status);
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for paths in spellings.values().filter(|paths| paths.len() > 1) {
+        let summary = paths
+            .iter()
+            .map(|(path, status)| {
+                let status = match (
+                    status.contains(MetaStatus::FILE),
+                    status.contains(MetaStatus::META),
+                ) {
+                    (true, true) => "asset and meta",
+                    (true, false) => "asset without meta",
+                    _ => "meta without asset",
+                };
+                format!("{} ({})", path.display(), status)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // the first spelling in path order is kept, like the case check keeps the first seen
+        for path in paths.keys().skip(1) {
+            let mut meta = path.clone().into_os_string();
+            meta.push(".meta");
+            if !filter_contains(filter, path) && !filter_contains(filter, Path::new(&meta)) {
+                continue;
+            }
+            emit(Finding::error(
+                Check::MetaCase,
+                path,
+                format!("collides on case-insensitive filesystems: {}", summary),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reports files whose extension is spelled with a different case than most files with that
+/// extension, e.g. `Logo.PNG` among `.png` files, since import settings and asset pipelines
+/// matching extensions case-sensitively treat them differently. Metas are skipped, their asset
+/// already carries the extension.
+fn test_extension_case(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { root, filter, .. } = *ctx;
+    info!("checking extension case consistency");
+
+    // lowercased extension -> paths by spelling of the extension
+    let mut spellings: HashMap<String, BTreeMap<String, Vec<PathBuf>>> = HashMap::new();
+    ctx.walk(|dir, entry| {
+        let name = match entry.name() {
+            Some(name) if !entry.is_submodule() => name,
+            _ => return TreeWalkResult::Skip,
+        };
+        let path = root.join(dir).join(name);
+        let is_dir = entry.kind() == Some(ObjectType::Tree);
+        if filter_excludes(filter, &path, is_dir) {
+            return TreeWalkResult::Skip;
+        }
+        if is_dir {
+            return TreeWalkResult::Ok;
+        }
+        let ext = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext != "meta" => ext.to_owned(),
+            _ => return TreeWalkResult::Ok,
+        };
+        spellings
+            .entry(ext.to_lowercase())
+            .or_default()
+            .entry(ext)
+            .or_default()
+            .push(path);
+        TreeWalkResult::Ok
+    })?;
+
+    for (lower, by_spelling) in spellings.iter().filter(|(_, paths)| paths.len() > 1) {
+        // the most used spelling wins, lowercase on ties
+        let (dominant, _) = by_spelling
+            .iter()
+            .max_by_key(|(spelling, paths)| (paths.len(), *spelling == lower))
+            .unwrap();
+        let total: usize = by_spelling.values().map(|paths| paths.len()).sum();
+        for (spelling, paths) in by_spelling
+            .iter()
+            .filter(|(spelling, _)| *spelling != dominant)
+        {
+            for path in paths.iter().filter(|path| filter_contains(filter, path)) {
+                emit(Finding::warning(
+                    Check::ExtensionCase,
+                    path,
+                    format!(
+                        "extension .{} differs from .{} used by {} of {} .{} files",
+                        spelling,
+                        dominant,
+                        by_spelling[dominant].len(),
+                        total,
+                        lower
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parsed content of a git-lfs pointer file.
+struct LfsPointer {
+    /// Value of the `oid sha256:` line as written, empty when missing.
+    oid: String,
+    /// `None` when the size line is missing or not a non-negative integer.
+    size: Option<u64>,
+    /// Byte offset of the first `\r`, pointers with CRLF line endings are rejected by some
+    /// git-lfs versions when smudging.
+    crlf_offset: Option<usize>,
+}
+
+const LFS_POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Parses a git-lfs pointer, returning `None` when the content doesn't start with the pointer
+/// version line. The other fields are kept as found so that malformed pointers can be reported.
+fn parse_lfs_pointer(content: &[u8]) -> Option<LfsPointer> {
+    let content = std::str::from_utf8(content).ok()?;
+    let mut lines = content.lines();
+    if lines.next()? != LFS_POINTER_VERSION {
+        return None;
+    }
+
+    let mut oid = String::new();
+    let mut size = None;
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = value.to_owned();
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+
+    Some(LfsPointer {
+        oid,
+        size,
+        crlf_offset: content.find('\r'),
+    })
+}
+
+/// git-lfs only accepts sha256 oids written as 64 lowercase hex characters.
+fn is_valid_lfs_oid(oid: &str) -> bool {
+    oid.len() == 64 && oid.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Where the lfs, per-type lfs, shader and binary checks read `.gitattributes` from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrSource {
+    /// The index, which matches the checked commit right after checkout or commit.
+    Index,
+    /// The working directory, then the index, for checks of uncommitted files.
+    Workdir,
+    /// The HEAD commit in addition to the index, or only HEAD in a bare repository.
+    Head,
+    /// The `.gitattributes` files of the checked tree, the only source matching a commit other
+    /// than the checked out one, e.g. the commits of `--since-tag`. With a subtree only the files
+    /// inside it are read.
+    Tree,
+}
+
+impl AttrSource {
+    /// Flags of `Repository::get_attr`, `None` for [`AttrSource::Tree`] which libgit2 can't read.
+    pub fn flags(self) -> Option<AttrCheckFlags> {
+        // libgit2's GIT_ATTR_CHECK_INCLUDE_HEAD, which git2 doesn't name
+        const INCLUDE_HEAD: u32 = 1 << 3;
+        match self {
+            AttrSource::Index => Some(AttrCheckFlags::INDEX_ONLY),
+            AttrSource::Workdir => Some(AttrCheckFlags::FILE_THEN_INDEX),
+            AttrSource::Head => {
+                Some(AttrCheckFlags::INDEX_ONLY | AttrCheckFlags::from_bits_retain(INCLUDE_HEAD))
+            }
+            AttrSource::Tree => None,
+        }
+    }
+}
+
+impl std::str::FromStr for AttrSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(AttrSource::Index),
+            "workdir" => Ok(AttrSource::Workdir),
+            "head" => Ok(AttrSource::Head),
+            "tree" => Ok(AttrSource::Tree),
+            _ => Err(format!("unknown attribute source: {}", s)),
+        }
+    }
+}
+
+/// Reads the attributes of checked paths from the [`AttrSource`] of the config, the
+/// `.gitattributes` of the tree being parsed once up front for [`AttrSource::Tree`].
+enum AttrReader {
+    Repository(AttrCheckFlags),
+    Tree(Attributes),
+}
+
+impl AttrReader {
+    fn new(config: &CheckConfig, ctx: &CheckContext) -> Result<Self> {
+        Ok(match config.attr_source.flags() {
+            Some(flags) => AttrReader::Repository(flags),
+            None => AttrReader::Tree(Attributes::from_tree_at(ctx.repo, ctx.tree, ctx.root)?),
+        })
+    }
+
+    /// Value of the attribute `name` for `path`, `repo` being the handle of the calling thread.
+    fn get(&self, repo: &Repository, path: &Path, name: &str) -> Result<AttrValue> {
+        let flags = match self {
+            AttrReader::Repository(flags) => *flags,
+            AttrReader::Tree(attributes) => return Ok(attributes.get(path, name)),
+        };
+        Ok(
+            match git2::AttrValue::from_string(repo.get_attr(path, name, flags)?) {
+                git2::AttrValue::True => AttrValue::Set,
+                git2::AttrValue::False => AttrValue::Unset,
+                git2::AttrValue::String(value) => AttrValue::Value(value.to_owned()),
+                git2::AttrValue::Bytes(value) => {
+                    AttrValue::Value(String::from_utf8_lossy(value).into_owned())
+                }
+                git2::AttrValue::Unspecified => AttrValue::Unspecified,
+            },
+        )
+    }
+
+    /// Whether `path` is merged by git-lfs, i.e. tracked by `git lfs track`.
+    fn is_lfs(&self, repo: &Repository, path: &Path) -> Result<bool> {
+        Ok(self.get(repo, path, "merge")? == AttrValue::Value("lfs".into()))
+    }
+}
+
+/// Directory of the local lfs object store, `.git/lfs/objects` unless moved with `lfs.storage`,
+/// which git-lfs resolves relative to the git directory.
+fn lfs_objects_dir(repo: &Repository) -> PathBuf {
+    let storage = repo
+        .config()
+        .and_then(|config| config.get_path("lfs.storage"))
+        .map(|storage| repo.path().join(storage))
+        .unwrap_or_else(|_| repo.path().join("lfs"));
+    storage.join("objects")
+}
+
+/// Path of the locally fetched object for a pointer, `<objects>/ab/cd/abcd...`.
+fn lfs_object_path(objects: &Path, oid: &str) -> Option<PathBuf> {
+    if oid.len() < 4 || !oid.is_ascii() {
+        return None;
+    }
+    Some(objects.join(&oid[0..2]).join(&oid[2..4]).join(oid))
+}
+
+/// Looks up the locally available LFS object of the pointer and, with `verify_size`, compares
+/// its size with the one declared by the pointer. Missing objects are only reported as warnings,
+/// they may just not be fetched yet.
+fn verify_lfs_object(
+    objects: &Path,
+    path: &Path,
+    pointer: &LfsPointer,
+    verify_size: bool,
+) -> Option<Finding> {
+    let object_path = lfs_object_path(objects, &pointer.oid)?;
+    match std::fs::metadata(object_path) {
+        Ok(metadata) if verify_size && pointer.size.is_some_and(|size| metadata.len() != size) => {
+            Some(Finding::error(
+                Check::Lfs,
+                path,
+                format!(
+                    "lfs object size mismatch, pointer={}, object={}",
+                    pointer.size.unwrap_or_default(),
+                    metadata.len()
+                ),
+            ))
+        }
+        Ok(_) => None,
+        Err(_) => Some(Finding::warning(
+            Check::Lfs,
+            path,
+            format!("lfs object not available locally, oid={}", pointer.oid),
+        )),
+    }
+}
+
+fn test_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    let verify_size = config.verify_lfs_size;
+    let verify_reachability = config.verify_lfs_reachability;
+    use rayon::prelude::*;
+
+    info!("checking invalid lfs files");
+
+    let objects = (verify_size || verify_reachability).then(|| lfs_objects_dir(repo));
+    // the attributes the tree was committed with, the index may have moved on since
+    let attributes = Attributes::from_tree_at(repo, tree, root)?;
+    let mut blobs = Vec::new();
+    collect_lfs_blobs(repo, root, tree, filter, &mut blobs, emit)?;
+
+    let reader = AttrReader::new(config, ctx)?;
+    let check_blob = |repo: &Repository, (path, id): &(PathBuf, Oid)| {
+        check_lfs_blob(
+            config,
+            repo,
+            path,
+            *id,
+            &attributes,
+            &reader,
+            objects.as_deref(),
+        )
+    };
+    let findings = if config.sequential {
+        blobs
+            .iter()
+            .map(|blob| check_blob(repo, blob))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        // reading blobs and attributes dominates on large trees, a repository handle isn't Sync
+        // so every chunk opens its own, a few chunks per thread to even out their sizes
+        let chunk_size = blobs
+            .len()
+            .div_ceil(rayon::current_num_threads() * 4)
+            .max(1);
+        let repo_path = repo.path();
+        blobs
+            .par_chunks(chunk_size)
+            .map(|chunk| {
+                let repo = open_repository(repo_path, &config.object_dirs)?;
+                chunk
+                    .iter()
+                    .map(|blob| check_blob(&repo, blob))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect()
+    };
+    // in tree order, whatever order the blobs were read in
+    for finding in findings.into_iter().flatten() {
+        emit(finding);
+    }
+    Ok(())
+}
+
+/// Collects the path and id of every blob of the tree the filter reports. Unreadable trees are
+/// reported instead of aborting the walk, so the other findings are kept and the failure counts
+/// towards the exit code.
+fn collect_lfs_blobs(
+    repo: &Repository,
+    prefix: &Path,
+    tree: &Tree,
+    filter: Option<&PathFilter>,
+    blobs: &mut Vec<(PathBuf, Oid)>,
+    emit: &mut Emit,
+) -> Result<()> {
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            None => continue,
+            Some(name) => name,
+        };
+        if is_submodule(&entry) {
+            continue;
+        }
+
+        let path = prefix.join(name);
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                if !filter_descends(filter, &path) {
+                    trace!("[lfs] {}: skip, unchanged", path.display());
+                    continue;
+                }
+                match repo.find_tree(entry.id()) {
+                    Ok(tree) => collect_lfs_blobs(repo, &path, &tree, filter, blobs, emit)?,
+                    Err(e) if filter_contains(filter, &path) => emit(Finding::error(
+                        Check::Lfs,
+                        path,
+                        format!("can't read object {}: {}", entry.id(), e.message()),
+                    )),
+                    Err(_) => {}
+                }
+            }
+            Some(ObjectType::Blob) => {
+                if !filter_contains(filter, &path) {
+                    trace!("[lfs] {}: skip, unchanged", path.display());
+                    continue;
+                }
+                blobs.push((path, entry.id()));
+            }
+            _ => {
+                continue;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Blobs from this size can't be lfs pointers, which are around 130 bytes.
+const LFS_POINTER_MAX_SIZE: usize = 150;
+
+/// Default of [`CheckConfig::lfs_threshold`], every blob too large to be a pointer.
+pub const LFS_THRESHOLD: usize = LFS_POINTER_MAX_SIZE;
+
+/// Findings of the lfs checks for the blob `id` at `path`.
+fn check_lfs_blob(
+    config: &CheckConfig,
+    repo: &Repository,
+    path: &Path,
+    id: Oid,
+    attributes: &Attributes,
+    reader: &AttrReader,
+    objects: Option<&Path>,
+) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    // a corrupt or missing object is reported like unreadable trees
+    let blob = match repo.find_blob(id) {
+        Ok(blob) => blob,
+        Err(e) => {
+            findings.push(Finding::error(
+                Check::Lfs,
+                path,
+                format!("can't read object {}: {}", id, e.message()),
+            ));
+            return Ok(findings);
+        }
+    };
+    let size = blob.size();
+
+    let is_lfs = reader.is_lfs(repo, path)?;
+    trace!("[lfs] {}: lfs={}, size={}", path.display(), is_lfs, size);
+    if !is_lfs {
+        // a pointer that a nested .gitattributes stopped tracking is checked out as is, the
+        // tree's own attributes tell whether it was committed that way; those above a --subtree
+        // aren't part of the tree, so both have to agree
+        let tracked = attributes.get(path, "filter") == AttrValue::Value("lfs".into());
+        if !tracked && size < LFS_POINTER_MAX_SIZE && parse_lfs_pointer(blob.content()).is_some() {
+            findings.push(Finding::error(
+                Check::Lfs,
+                path,
+                "lfs pointer not tracked by filter=lfs in the .gitattributes of its directory, it won't be smudged".into(),
+            ));
+        }
+        return Ok(findings);
+    }
+
+    // larger blobs weren't converted by git-lfs
+    let pointer = Some(blob.content())
+        .filter(|_| size < LFS_POINTER_MAX_SIZE)
+        .and_then(parse_lfs_pointer);
+    if pointer.is_none() && size >= config.lfs_threshold {
+        findings.push(Finding::error(
+            Check::Lfs,
+            path,
+            format!("should be in LFS, size={}", size),
+        ));
+        return Ok(findings);
+    }
+    if let Some(pointer) = pointer {
+        if !is_valid_lfs_oid(&pointer.oid) {
+            findings.push(Finding::error(
+                Check::LfsInvalidOid,
+                path,
+                format!(
+                    "invalid lfs pointer oid {:?}, expected 64 lowercase hex characters",
+                    pointer.oid
+                ),
+            ));
+        }
+        if pointer.size.is_none() {
+            findings.push(Finding::error(
+                Check::LfsInvalidOid,
+                path,
+                "invalid lfs pointer size, expected a non-negative integer".into(),
+            ));
+        }
+        if let Some(offset) = pointer.crlf_offset {
+            findings.push(Finding::warning(
+                Check::Lfs,
+                path,
+                format!(
+                    "lfs pointer has CRLF line endings, first CR at byte {}",
+                    offset
+                ),
+            ));
+        }
+        if let Some(objects) = objects {
+            findings.extend(verify_lfs_object(
+                objects,
+                path,
+                &pointer,
+                config.verify_lfs_size,
+            ));
+        }
+    }
+    Ok(findings)
+}
+
+/// Extensions of 3D model files, which are binary and usually large.
+const MODEL_EXTENSIONS: &[&str] = &["fbx", "obj", "dae", "blend", "max", "ma"];
+
+/// Model files above this size must be in LFS.
+const MODEL_LFS_THRESHOLD: usize = 10_000;
+
+/// Paths and ids of the blobs with one of the given lowercase extensions.
+/// The blob `id` at `path`, or `None` once a corrupt or missing object is reported under
+/// `check`, like the lfs check does, so that one bad object doesn't hide the other findings.
+fn read_blob<'r>(
+    repo: &'r Repository,
+    check: Check,
+    path: &Path,
+    id: Oid,
+    emit: &mut Emit,
+) -> Option<Blob<'r>> {
+    match repo.find_blob(id) {
+        Ok(blob) => Some(blob),
+        Err(e) => {
+            emit(Finding::error(
+                check,
+                path,
+                format!("can't read object {}: {}", id, e.message()),
+            ));
+            None
+        }
+    }
+}
+
+fn extension_blobs(ctx: &CheckContext, extensions: &[&str]) -> Result<Vec<(PathBuf, Oid)>> {
+    let CheckContext { root, filter, .. } = *ctx;
+    let mut blobs = Vec::new();
+    ctx.walk(|dir, entry| {
+        if entry.kind() == Some(ObjectType::Tree) {
+            let path = root.join(dir).join(entry.name().unwrap_or(""));
+            if !filter_descends(filter, &path) {
+                return TreeWalkResult::Skip;
+            }
+        }
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = root.join(dir).join(name);
+            let matches = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()));
+            if matches && filter_contains(filter, &path) {
+                blobs.push((path, entry.id()));
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+    Ok(blobs)
+}
+
+/// Reports files with one of the given extensions larger than `threshold` bytes that aren't
+/// stored in LFS, i.e. tracked by an lfs attribute and committed as a pointer. Unlike the lfs
+/// check this doesn't depend on the extension being listed in `.gitattributes` in the first
+/// place.
+fn test_extension_lfs(
+    config: &CheckConfig,
+    ctx: &CheckContext,
+    check: Check,
+    extensions: &[&str],
+    threshold: usize,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext { repo, .. } = *ctx;
+    let reader = AttrReader::new(config, ctx)?;
+
+    for (path, id) in extension_blobs(ctx, extensions)? {
+        let blob = match read_blob(repo, check, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        let size = blob.size();
+        if size <= threshold {
+            trace!(
+                "[{}] {}: skip, size={} is below the threshold",
+                check.name(),
+                path.display(),
+                size
+            );
+            continue;
+        }
+        let is_lfs = reader.is_lfs(repo, &path)?;
+        trace!(
+            "[{}] {}: lfs={}, size={}",
+            check.name(),
+            path.display(),
+            is_lfs,
+            size
+        );
+        if !is_lfs {
+            emit(Finding::error(
+                check,
+                path,
+                format!("should be in LFS, size={}", size),
+            ));
+        } else if parse_lfs_pointer(blob.content()).is_none() {
+            emit(Finding::error(
+                check,
+                path,
+                format!(
+                    "tracked by LFS but not committed as a pointer, size={}",
+                    size
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn test_fbx_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    info!("checking model files in lfs");
+    test_extension_lfs(
+        config,
+        ctx,
+        Check::Fbx,
+        MODEL_EXTENSIONS,
+        MODEL_LFS_THRESHOLD,
+        emit,
+    )
+}
+
+/// Extensions of texture and image files.
+const TEXTURE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "tga", "psd", "exr", "hdr", "tif", "tiff", "bmp",
+];
+
+/// Default size above which textures must be in LFS.
+pub const TEXTURE_LFS_THRESHOLD: usize = 512 * 1024;
+
+/// Small textures such as UI icons are fine outside of LFS, larger ones are reported.
+fn test_texture_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let threshold = config.texture_lfs_threshold;
+    info!("checking texture files in lfs");
+    test_extension_lfs(
+        config,
+        ctx,
+        Check::Texture,
+        TEXTURE_EXTENSIONS,
+        threshold,
+        emit,
+    )
+}
+
+/// Extensions of audio files.
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "mp3", "ogg", "aiff", "flac"];
+
+/// Default size above which audio files must be in LFS.
+pub const AUDIO_LFS_THRESHOLD: usize = 0;
+
+/// Even short audio clips are large, so by default every non-empty audio file must be in LFS.
+fn test_audio_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let threshold = config.audio_lfs_threshold;
+    info!("checking audio files in lfs");
+    test_extension_lfs(config, ctx, Check::Audio, AUDIO_EXTENSIONS, threshold, emit)
+}
+
+/// Extensions of video files.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "avi", "webm", "m4v"];
+
+/// Default size above which videos must be in LFS.
+pub const VIDEO_LFS_THRESHOLD: usize = 0;
+
+/// Videos are the largest source of repository bloat, so by default every video must be in LFS.
+fn test_video_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let threshold = config.video_lfs_threshold;
+    info!("checking video files in lfs");
+    test_extension_lfs(config, ctx, Check::Video, VIDEO_EXTENSIONS, threshold, emit)
+}
+
+/// Extensions of Unity scenes and prefabs.
+const SCENE_EXTENSIONS: &[&str] = &["unity", "prefab"];
+
+/// Default size above which scenes and prefabs must be in LFS.
+pub const SCENE_LFS_THRESHOLD: usize = 5 * 1024 * 1024;
+
+/// Scenes and prefabs above the threshold should be in LFS, and all of them should use Unity's
+/// text serialization, which starts with a `%YAML 1.1` header, so they can be diffed and merged.
+fn test_scene_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { repo, .. } = *ctx;
+    let threshold = config.scene_lfs_threshold;
+    info!("checking scene and prefab files");
+    test_extension_lfs(config, ctx, Check::Scene, SCENE_EXTENSIONS, threshold, emit)?;
+
+    for (path, id) in extension_blobs(ctx, SCENE_EXTENSIONS)? {
+        // unreadable objects were reported by test_extension_lfs already
+        let blob = match repo.find_blob(id) {
+            Ok(blob) => blob,
+            Err(_) => continue,
+        };
+        let content = blob.content();
+        // the serialization of files in LFS can't be checked from the pointer
+        if content.is_empty() || parse_lfs_pointer(content).is_some() {
+            continue;
+        }
+        if !content.starts_with(b"%YAML 1.1") {
+            emit(Finding::error(
+                Check::Scene,
+                path,
+                "not serialized as text, set Asset Serialization to Force Text".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Extensions of shader sources and include libraries.
+const SHADER_EXTENSIONS: &[&str] = &["shader", "hlsl", "glsl", "cginc", "compute"];
+
+/// Default size above which shaders are suggested for LFS.
+pub const SHADER_LFS_THRESHOLD: usize = 1024 * 1024;
+
+/// Shaders are text and normally don't belong in LFS, but generated permutation files above the
+/// threshold are reported as candidates, and shaders that are binary by their attributes or
+/// content, or that aren't UTF-8, must be in LFS.
+fn test_shader_lfs(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo, tree, root, ..
+    } = *ctx;
+    let threshold = config.shader_lfs_threshold;
+    info!("checking shader files");
+
+    let reader = AttrReader::new(config, ctx)?;
+    let attributes = Attributes::from_tree_at(repo, tree, root)?;
+    for (path, id) in extension_blobs(ctx, SHADER_EXTENSIONS)? {
+        if reader.is_lfs(repo, &path)? {
+            continue;
+        }
+        let blob = match read_blob(repo, Check::Shader, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if !is_text(&attributes, &path, blob.content()) {
+            emit(Finding::error(
+                Check::Shader,
+                path,
+                "binary and should be in LFS".into(),
+            ));
+        } else if let Err(e) = std::str::from_utf8(blob.content()) {
+            emit(Finding::error(
+                Check::Shader,
+                path,
+                format!(
+                    "not valid utf-8 at byte {}, likely binary and should be in LFS",
+                    e.valid_up_to()
+                ),
+            ));
+        } else if blob.size() > threshold {
+            emit(Finding::warning(
+                Check::Shader,
+                path,
+                format!("large shader, consider LFS, size={}", blob.size()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Default size above which assets in a `Resources` folder are reported.
+pub const RESOURCES_THRESHOLD: usize = 1024 * 1024;
+
+/// Size of the asset a blob stores, the declared size for lfs pointers.
+fn asset_size(blob: &Blob) -> usize {
+    match parse_lfs_pointer(blob.content()).and_then(|pointer| pointer.size) {
+        Some(size) => size as usize,
+        None => blob.size(),
+    }
+}
+
+/// Reports assets larger than `threshold` bytes in a `Resources` folder under the asset roots.
+/// Unity includes everything in `Resources` in every build whether it's used or not, so large
+/// assets there bloat builds and startup time. This is a best practice rather than an error.
+fn test_resources_folder(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo, root, filter, ..
+    } = *ctx;
+    let asset_roots = &config.asset_roots;
+    let threshold = config.resources_threshold;
+    info!("checking large assets in Resources folders");
+
+    let mut blobs = Vec::new();
+    ctx.walk(|dir, entry| {
+        if entry.kind() == Some(ObjectType::Tree) {
+            let path = root.join(dir).join(entry.name().unwrap_or(""));
+            if !filter_descends(filter, &path) {
+                return TreeWalkResult::Skip;
+            }
+        }
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = root.join(dir).join(name);
+            // any folder named Resources counts, at any depth
+            let in_resources = path
+                .parent()
+                .is_some_and(|dir| dir.components().any(|c| c.as_os_str() == "Resources"));
+            if in_resources
+                && !name.ends_with(".meta")
+                && asset_roots.contains(&path)
+                && filter_contains(filter, &path)
+            {
+                blobs.push((path, entry.id()));
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for (path, id) in blobs {
+        let size = match read_blob(repo, Check::Resources, &path, id, emit) {
+            Some(blob) => asset_size(&blob),
+            None => continue,
+        };
+        if size > threshold {
+            emit(Finding::warning(
+                Check::Resources,
+                path,
+                format!(
+                    "large asset in Resources is included in every build, size={}",
+                    size
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Default size above which files in `StreamingAssets` must be in LFS.
+pub const STREAMING_ASSETS_LFS_THRESHOLD: usize = 1024 * 1024;
+
+/// Reports files larger than `threshold` bytes in the `StreamingAssets` folder of an asset root
+/// that aren't lfs pointers. Unity copies the folder verbatim to the device, it's meant for large
+/// files loaded at runtime, so unlike the lfs check this doesn't depend on `.gitattributes`.
+fn test_streaming_assets(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo, root, filter, ..
+    } = *ctx;
+    let asset_roots = &config.asset_roots;
+    let threshold = config.streaming_assets_lfs_threshold;
+    info!("checking StreamingAssets files");
+
+    let folders = asset_roots
+        .iter()
+        .map(|asset_root| asset_root.join("StreamingAssets"))
+        .collect::<Vec<_>>();
+    let mut blobs = Vec::new();
+    ctx.walk(|dir, entry| {
+        let path = root.join(dir).join(entry.name().unwrap_or(""));
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                // only descend towards or into the StreamingAssets folders
+                let on_the_way = folders
+                    .iter()
+                    .any(|folder| path.starts_with(folder) || folder.starts_with(&path));
+                if !on_the_way || !filter_descends(filter, &path) {
+                    return TreeWalkResult::Skip;
+                }
+            }
+            Some(ObjectType::Blob) => {
+                let in_folder = folders.iter().any(|folder| path.starts_with(folder));
+                if in_folder
+                    && path.extension() != Some("meta".as_ref())
+                    && filter_contains(filter, &path)
+                {
+                    blobs.push((path, entry.id()));
+                }
+            }
+            _ => {}
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for (path, id) in blobs {
+        let blob = match read_blob(repo, Check::StreamingAssets, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if blob.size() <= threshold || parse_lfs_pointer(blob.content()).is_some() {
+            continue;
+        }
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        emit(Finding::error(
+            Check::StreamingAssets,
+            path,
+            format!(
+                "large file in StreamingAssets not in LFS, size={}, add `*.{} filter=lfs diff=lfs merge=lfs -text` to .gitattributes",
+                blob.size(),
+                ext
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Reports blobs that git would treat as binary, a NUL byte within the first 8000 bytes, but
+/// that aren't covered by `filter=lfs`. These usually have an extension missing from
+/// `.gitattributes`. Blobs that are already lfs pointers are skipped, and so are those the
+/// committed `.gitattributes` mark as `text`.
+fn test_binary_text_mismatch(
+    config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking binary files outside of lfs");
+
+    let reader = AttrReader::new(config, ctx)?;
+    let attributes = Attributes::from_tree_at(repo, tree, root)?;
+    let mut blobs = Vec::new();
+    ctx.walk(|dir, entry| {
+        if entry.kind() == Some(ObjectType::Tree) {
+            let path = root.join(dir).join(entry.name().unwrap_or(""));
+            if !filter_descends(filter, &path) {
+                return TreeWalkResult::Skip;
+            }
+        }
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = root.join(dir).join(name);
+            if filter_contains(filter, &path) {
+                blobs.push((path, entry.id()));
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for (path, id) in blobs {
+        let blob = match read_blob(repo, Check::Binary, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        let content = blob.content();
+        if is_text(&attributes, &path, content) {
+            continue;
+        }
+        // files marked binary without a NUL byte are left to git
+        let head = &content[..content.len().min(BINARY_DETECTION_BYTES)];
+        let offset = match head.iter().position(|&b| b == 0) {
+            Some(offset) => offset,
+            None => continue,
+        };
+        let attr = reader.get(repo, &path, "filter")?;
+        trace!(
+            "[binary] {}: NUL at byte {}, filter={:?}",
+            path.display(),
+            offset,
+            attr
+        );
+        if attr == AttrValue::Value("lfs".into()) || parse_lfs_pointer(content).is_some() {
+            continue;
+        }
+        emit(Finding::error(
+            Check::Binary,
+            path,
+            format!(
+                "binary content (NUL at byte {}) without filter=lfs, add the file type to .gitattributes",
+                offset
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Extensions of the assets Unity serializes as YAML text or in its binary format.
+const UNITY_ASSET_EXTENSIONS: &[&str] = &["asset", "prefab", "unity", "anim", "controller"];
+
+/// Leading bytes of assets in Unity's binary serialization format.
+const UNITY_BINARY_SIGNATURE: &[u8] = b"\0\0\0\0\0\0\0\0\x14\0\0\0\0\0\0\0";
+
+/// Reports Unity assets in the binary serialization format committed outside of LFS, they can't
+/// be diffed nor merged so git gains nothing from storing them as regular blobs.
+fn test_binary_unity_assets(
+    _config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext { repo, .. } = *ctx;
+    info!("checking binary serialized unity assets");
+
+    for (path, id) in extension_blobs(ctx, UNITY_ASSET_EXTENSIONS)? {
+        let blob = match read_blob(repo, Check::UnityBinary, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        // pointers never start with the signature, so assets in LFS are skipped
+        if !blob.content().starts_with(UNITY_BINARY_SIGNATURE) {
+            continue;
+        }
+        emit(Finding::error(
+            Check::UnityBinary,
+            path,
+            "binary serialized asset not in LFS".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Extensions of source and text files which should never be stored in LFS, since pointers
+/// can't be diffed or reviewed.
+pub const LFS_TEXT_EXTENSIONS: &[&str] = &["cs", "json", "xml", "txt", "asmdef"];
+
+/// Reports files with one of the given extensions committed as LFS pointers, usually matched by
+/// an lfs pattern in `.gitattributes` that is broader than intended.
+fn test_lfs_text(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { repo, .. } = *ctx;
+    let extensions = &config.lfs_text_extensions;
+    info!("checking text files in lfs");
+
+    let extensions = extensions
+        .iter()
+        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+        .collect::<Vec<_>>();
+    let extensions = extensions.iter().map(String::as_str).collect::<Vec<_>>();
+    for (path, id) in extension_blobs(ctx, &extensions)? {
+        let blob = match read_blob(repo, Check::LfsText, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if parse_lfs_pointer(blob.content()).is_none() {
+            trace!("[lfs-text] {}: ok, not a pointer", path.display());
+            continue;
+        }
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        emit(Finding::error(
+            Check::LfsText,
+            path,
+            format!(
+                "text file with extension .{} committed as an lfs pointer, exclude it from the lfs patterns in .gitattributes",
+                ext
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Reports `.gitattributes` patterns that set `filter=lfs` without `-text`. Such files can get
+/// their line endings normalized before the lfs filter runs, corrupting binaries, which is why
+/// `git lfs track` always writes `-text`.
+fn test_lfs_attributes(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking lfs patterns in .gitattributes");
+
+    let attributes = Attributes::from_tree(repo, tree)?;
+    for rule in attributes.rules() {
+        if rule.get("filter") != Some(&AttrValue::Value("lfs".into()))
+            || rule.get("text") == Some(&AttrValue::Unset)
+        {
+            continue;
+        }
+        let path = root.join(&rule.source);
+        if !filter_contains(filter, &path) {
+            continue;
+        }
+        emit(Finding::warning(
+            Check::LfsAttributes,
+            path,
+            format!(
+                "line {}: {:?} sets filter=lfs without -text, line endings may be converted before the lfs filter",
+                rule.line, rule.pattern
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Reports files of the tree locked on the lfs server by someone other than the current user,
+/// whose changes to them would be rejected on push.
+fn test_lfs_locks(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking lfs locks");
+
+    let url = locks::lfs_url(repo)?;
+    let user = locks::current_user(repo);
+    debug!("fetching lfs locks from {}, user={:?}", url, user);
+
+    for lock in locks::fetch_locks(&url)? {
+        let owner = lock.owner.as_ref().map_or("unknown", |owner| &owner.name);
+        let path = PathBuf::from(&lock.path);
+        if user.as_deref() == Some(owner) {
+            trace!(
+                "[lfs-locks] {}: skip, locked by the current user",
+                path.display()
+            );
+            continue;
+        }
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        match tree.get_path(relative) {
+            Ok(_) => {}
+            Err(e) if e.code() == ErrorCode::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+        if !filter_contains(filter, &path) {
+            continue;
+        }
+        emit(Finding::error(
+            Check::LfsLocks,
+            path,
+            format!(
+                "locked by {} since {}, lock id {}",
+                owner, lock.locked_at, lock.id
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Importers Unity assigns by default, keyed by lowercase file extension.
+const DEFAULT_IMPORTERS: &[(&str, &str)] = &[
+    ("png", "TextureImporter"),
+    ("jpg", "TextureImporter"),
+    ("jpeg", "TextureImporter"),
+    ("tga", "TextureImporter"),
+    ("psd", "TextureImporter"),
+    ("exr", "TextureImporter"),
+    ("hdr", "TextureImporter"),
+    ("tif", "TextureImporter"),
+    ("tiff", "TextureImporter"),
+    ("bmp", "TextureImporter"),
+    ("fbx", "ModelImporter"),
+    ("obj", "ModelImporter"),
+    ("dae", "ModelImporter"),
+    ("blend", "ModelImporter"),
+    ("wav", "AudioImporter"),
+    ("mp3", "AudioImporter"),
+    ("ogg", "AudioImporter"),
+    ("aiff", "AudioImporter"),
+    ("flac", "AudioImporter"),
+    ("mp4", "VideoClipImporter"),
+    ("mov", "VideoClipImporter"),
+    ("webm", "VideoClipImporter"),
+    ("cs", "MonoImporter"),
+    ("shader", "ShaderImporter"),
+    ("asmdef", "AssemblyDefinitionImporter"),
+];
+
+/// Expected importer by extension, built from the defaults and overridden by `ext=Importer`
+/// pairs from the command line.
+pub fn importer_map(overrides: &[String]) -> Result<HashMap<String, String>, CheckError> {
+    let mut importers: HashMap<String, String> = DEFAULT_IMPORTERS
+        .iter()
+        .map(|(ext, importer)| (ext.to_string(), importer.to_string()))
+        .collect();
+    for item in overrides {
+        match item.split_once('=') {
+            Some((ext, importer)) => {
+                importers.insert(importer_extension(ext), importer.into());
+            }
+            None => {
+                return Err(CheckError::Parse(format!(
+                    "invalid importer mapping {:?}, expected ext=Importer",
+                    item
+                )))
+            }
+        }
+    }
+    Ok(importers)
+}
+
+/// The key of `ext` in the importer map, lowercase and without the dot, e.g. `png` for `.PNG`.
+fn importer_extension(ext: &str) -> String {
+    ext.trim_start_matches('.').to_lowercase()
+}
+
+/// Finds the first top-level `*Importer:` key of a meta file, without parsing the whole YAML.
+fn meta_importer(content: &[u8]) -> Option<&str> {
+    let content = std::str::from_utf8(content).ok()?;
+    content.lines().find_map(|line| {
+        if line.starts_with([' ', '\t']) {
+            return None;
+        }
+        line.trim_end()
+            .strip_suffix(':')
+            .filter(|key| key.ends_with("Importer"))
+    })
+}
+
+fn test_importer(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    let importers = &config.importers;
+    let asset_roots = &config.asset_roots;
+    info!("checking meta importers");
+
+    iter_tree_importer(repo, root, tree, filter, importers, asset_roots, emit)?;
+    Ok(())
+}
+
+fn iter_tree_importer(
+    repo: &Repository,
+    prefix: &Path,
+    tree: &Tree,
+    filter: Option<&PathFilter>,
+    importers: &HashMap<String, String>,
+    asset_roots: &AssetRoots,
+    emit: &mut Emit,
+) -> Result<()> {
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            None => continue,
+            Some(name) => name,
+        };
+        let path = prefix.join(name);
+
+        match entry.kind() {
+            Some(ObjectType::Tree) if filter_descends(filter, &path) => {
+                let tree = entry.to_object(repo)?.peel_to_tree()?;
+                iter_tree_importer(repo, &path, &tree, filter, importers, asset_roots, emit)?;
+            }
+            Some(ObjectType::Blob) => {
+                let base_name = match name.strip_suffix(".meta") {
+                    Some(base_name) => base_name,
+                    None => continue,
+                };
+                let expected = match Path::new(base_name)
+                    .extension()
+                    .and_then(|ext| importers.get(&ext.to_string_lossy().to_lowercase()))
+                {
+                    Some(expected) => expected,
+                    None => continue,
+                };
+                if !asset_roots.contains(&path) || !filter_contains(filter, &path) {
+                    continue;
+                }
+
+                let blob = entry.to_object(repo)?.peel_to_blob()?;
+                match meta_importer(blob.content()) {
+                    Some(importer) if importer == expected => {}
+                    Some(importer) => emit(Finding::warning(
+                        Check::Importer,
+                        path,
+                        format!("unexpected importer {}, expected {}", importer, expected),
+                    )),
+                    None => emit(Finding::warning(
+                        Check::Importer,
+                        path,
+                        format!("missing importer, expected {}", expected),
+                    )),
+                }
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+/// Unity writes metas with LF line endings, metas rewritten with CRLF by an editor cause diff
+/// churn and merge conflicts. Metas the committed `.gitattributes` mark as binary are skipped.
+fn test_meta_line_endings(
+    _config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo, tree, root, ..
+    } = *ctx;
+    info!("checking meta line endings");
+
+    let attributes = Attributes::from_tree_at(repo, tree, root)?;
+    for (path, id) in extension_blobs(ctx, &["meta"])? {
+        let blob = match read_blob(repo, Check::MetaLineEndings, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if !is_text(&attributes, &path, blob.content()) {
+            trace!("[meta-line-endings] {}: skip, binary", path.display());
+            continue;
+        }
+        let crlf_lines = blob.content().windows(2).filter(|w| w == b"\r\n").count();
+        if crlf_lines > 0 {
+            emit(Finding::error(
+                Check::MetaLineEndings,
+                path,
+                format!("{} lines end with CRLF, unity writes LF", crlf_lines),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the first line of every meta, which is `fileFormatVersion: 2` for current Unity
+/// versions. Version 1 is outdated but still imported, anything else fails silently on import.
+fn test_meta_version(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { repo, .. } = *ctx;
+    info!("checking meta file format versions");
+
+    for (path, id) in extension_blobs(ctx, &["meta"])? {
+        let blob = match read_blob(repo, Check::MetaVersion, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        let first_line = blob.content().split(|&b| b == b'\n').next().unwrap_or(&[]);
+        let first_line = String::from_utf8_lossy(first_line);
+        match first_line.trim_end() {
+            "fileFormatVersion: 2" => {}
+            "fileFormatVersion: 1" => emit(Finding::warning(
+                Check::MetaVersion,
+                path,
+                "outdated fileFormatVersion: 1, reimport the asset to upgrade it".into(),
+            )),
+            line => emit(Finding::error(
+                Check::MetaVersion,
+                path,
+                format!(
+                    "expected fileFormatVersion: 2 on the first line, found {:?}",
+                    line
+                ),
+            )),
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `guid: <hex>` line of a meta file.
+fn meta_guid(content: &[u8]) -> Option<&str> {
+    let content = std::str::from_utf8(content).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("guid:"))
+        .map(str::trim)
+        .filter(|guid| !guid.is_empty())
+}
+
+/// GUIDs declared by the metas under the asset roots, with the path of the asset each belongs to.
+fn collect_meta_guids(
+    repo: &Repository,
+    tree: &Tree,
+    asset_roots: &AssetRoots,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut guids = Vec::new();
+    let mut error = None;
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        let name = match entry.name() {
+            Some(name) if entry.kind() == Some(ObjectType::Blob) => name,
+            _ => return TreeWalkResult::Ok,
+        };
+        let base_name = match name.strip_suffix(".meta") {
+            Some(base_name) => base_name,
+            None => return TreeWalkResult::Ok,
+        };
+        let path = Path::new(root).join(base_name);
+        if !asset_roots.contains(&path) {
+            return TreeWalkResult::Ok;
+        }
+        match repo.find_blob(entry.id()) {
+            Ok(blob) => {
+                if let Some(guid) = meta_guid(blob.content()) {
+                    guids.push((guid.to_owned(), path));
+                }
+                TreeWalkResult::Ok
+            }
+            Err(e) => {
+                error = Some(e);
+                TreeWalkResult::Abort
+            }
+        }
+    })?;
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok(guids),
+    }
+}
+
+/// Default limit of the component-length check, the name limit of most filesystems.
+pub const MAX_COMPONENT_LENGTH: usize = 255;
+
+/// Reports file and directory names longer than `max_length` bytes. Filesystems limit the bytes
+/// of a name rather than its characters, so names in non-ASCII scripts hit the limit sooner; the
+/// total length of a path is a separate limit this doesn't check.
+fn test_component_length(config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext { root, filter, .. } = *ctx;
+    let max_length = config.max_component_length;
+    info!("checking path component lengths");
+
+    ctx.walk(|dir, entry| {
+        let name = entry.name_bytes();
+        let path = root.join(dir).join(String::from_utf8_lossy(name).as_ref());
+        if entry.kind() == Some(ObjectType::Tree) && !filter_descends(filter, &path) {
+            return TreeWalkResult::Skip;
+        }
+        if name.len() > max_length && filter_contains(filter, &path) {
+            emit(Finding::error(
+                Check::ComponentLength,
+                &path,
+                format!(
+                    "name {:?} is {} bytes, longer than {} bytes",
+                    String::from_utf8_lossy(name),
+                    name.len(),
+                    max_length
+                ),
+            ));
+        }
+        TreeWalkResult::Ok
+    })?;
+    Ok(())
+}
+
+/// Reports GUIDs used for different paths on different branches, which happens when two
+/// branches generate metas independently and breaks references once they are merged. A GUID
+/// already present in the merge base of the branches is a rename rather than a collision and
+/// is not reported.
+pub fn test_duplicate_guids_across_commits(
+    config: &CheckConfig,
+    repo: &Repository,
+    tips: &[(String, Commit)],
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let asset_roots = &config.asset_roots;
+    info!("checking guids across {} branches", tips.len());
+
+    // guid -> (index of the first branch using it, path)
+    let mut registry: HashMap<String, (usize, PathBuf)> = HashMap::new();
+    let mut base_guids: HashMap<Oid, HashSet<String>> = HashMap::new();
+
+    for (index, (branch, commit)) in tips.iter().enumerate() {
+        for (guid, path) in collect_meta_guids(repo, &commit.tree()?, asset_roots)? {
+            let (first_index, first_path) = match registry.get(&guid) {
+                None => {
+                    registry.insert(guid, (index, path));
+                    continue;
+                }
+                Some((first_index, first_path)) => (*first_index, first_path),
+            };
+            if first_index == index || *first_path == path {
+                continue;
+            }
+
+            let (first_branch, first_commit) = &tips[first_index];
+            let base = repo.merge_base(first_commit.id(), commit.id()).ok();
+            if let Some(base) = base {
+                if let Entry::Vacant(entry) = base_guids.entry(base) {
+                    let tree = repo.find_commit(base)?.tree()?;
+                    let guids = collect_meta_guids(repo, &tree, asset_roots)?;
+                    entry.insert(guids.into_iter().map(|(guid, _)| guid).collect());
+                }
+                if base_guids[&base].contains(&guid) {
+                    continue;
+                }
+            }
+
+            emit(Finding::error(
+                Check::Guid,
+                &path,
+                format!(
+                    "guid {} on branch {} is also used by {} on branch {}",
+                    guid,
+                    branch,
+                    first_path.display(),
+                    first_branch
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reports metas under the asset roots whose GUID differs between `old` and `new`, e.g. a meta
+/// deleted and regenerated by Unity, which breaks every reference to the asset. Renamed metas
+/// are compared with their previous path so that moving an asset keeps its GUID too.
+pub fn test_guid_stability(
+    config: &CheckConfig,
+    repo: &Repository,
+    old: &Tree,
+    new: &Tree,
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let asset_roots = &config.asset_roots;
+    info!(
+        "checking guids changed between {} and {}",
+        old.id(),
+        new.id()
+    );
+
+    let mut diff = repo.diff_tree_to_tree(Some(old), Some(new), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    for delta in diff.deltas() {
+        if !matches!(delta.status(), Delta::Modified | Delta::Renamed) {
+            continue;
+        }
+        let path = match delta.new_file().path() {
+            Some(path) if path.extension().is_some_and(|ext| ext == "meta") => path,
+            _ => continue,
+        };
+        if !asset_roots.contains(&path.with_extension("")) {
+            continue;
+        }
+
+        let old_blob = repo.find_blob(delta.old_file().id())?;
+        let new_blob = repo.find_blob(delta.new_file().id())?;
+        let (old_guid, new_guid) =
+            match (meta_guid(old_blob.content()), meta_guid(new_blob.content())) {
+                (Some(old_guid), Some(new_guid)) => (old_guid, new_guid),
+                // a meta without guid is reported by the meta check
+                _ => continue,
+            };
+        if old_guid != new_guid {
+            emit(Finding::error(
+                Check::GuidStability,
+                path,
+                format!("guid changed from {} to {}", old_guid, new_guid),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reports assets under the asset roots whose blob and meta first appear in different commits of
+/// `base..head`, when the commit adding the asset or the meta alone is checked out the project
+/// is briefly missing a meta or has a dangling one. Directories appear with the first file added
+/// to them. Assets or metas already in `base` aren't reported.
+pub fn test_meta_commits(
+    config: &CheckConfig,
+    repo: &Repository,
+    base: &Commit,
+    head: &Commit,
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let asset_roots = &config.asset_roots;
+    info!(
+        "checking metas committed apart from their asset since {}",
+        base.id()
+    );
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push(head.id())?;
+    revwalk.hide(base.id())?;
+
+    // path -> first commit adding it, for assets and for metas by their asset path
+    let mut assets: BTreeMap<PathBuf, Oid> = BTreeMap::new();
+    let mut metas: HashMap<PathBuf, Oid> = HashMap::new();
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if delta.status() != Delta::Added {
+                continue;
+            }
+            let path = match delta.new_file().path() {
+                Some(path) => path,
+                None => continue,
+            };
+            match path.to_str().and_then(|path| path.strip_suffix(".meta")) {
+                Some(asset) => {
+                    metas.entry(PathBuf::from(asset)).or_insert(commit.id());
+                }
+                None => {
+                    for path in path
+                        .ancestors()
+                        .take_while(|path| asset_roots.contains(path))
+                    {
+                        assets.entry(path.to_owned()).or_insert(commit.id());
+                    }
+                }
+            }
+        }
+    }
+
+    for (path, asset_commit) in &assets {
+        let meta_commit = match metas.get(path) {
+            Some(meta_commit) => meta_commit,
+            None => continue,
+        };
+        if meta_commit != asset_commit {
+            emit(Finding::warning(
+                Check::MetaCommit,
+                path,
+                format!(
+                    "first committed in {} but its meta in {}",
+                    asset_commit, meta_commit
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reports assets under the asset roots staged without their meta, or metas staged without their
+/// asset, while the other one exists in the working directory, e.g. after `git add *.png`. The
+/// meta check only sees the staged tree and reports a missing meta without telling that it just
+/// isn't staged. `filter` limits reporting to the staged changes.
+pub fn test_staged_metas(
+    config: &CheckConfig,
+    repo: &Repository,
+    index: &Index,
+    filter: Option<&PathFilter>,
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let asset_roots = &config.asset_roots;
+    let ignore_fn = &*config.meta_ignore;
+    info!("checking partially staged metas");
+
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir,
+        None => return Ok(()),
+    };
+    // the stage is stored in bits 12 and 13 of the flags, 0 for entries without conflict
+    let paths = index
+        .iter()
+        .filter(|entry| (entry.flags >> 12) & 0x3 == 0)
+        .filter_map(|entry| String::from_utf8(entry.path).ok())
+        .collect::<Vec<_>>();
+    let staged = paths.iter().cloned().collect::<HashSet<_>>();
+
+    for path in &paths {
+        let (asset, meta) = match path.strip_suffix(".meta") {
+            Some(asset) => (asset.to_owned(), path.clone()),
+            None => (path.clone(), format!("{}.meta", path)),
+        };
+        let name = Path::new(&asset)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if ignore_fn(name) || !asset_roots.contains(Path::new(&asset)) {
+            continue;
+        }
+        if !filter_contains(filter, Path::new(&asset)) && !filter_contains(filter, Path::new(&meta))
+        {
+            continue;
+        }
+
+        if *path == asset && !staged.contains(&meta) && workdir.join(&meta).is_file() {
+            emit(Finding::error(
+                Check::StagedMeta,
+                &asset,
+                format!("staged without its meta, {} isn't staged", meta),
+            ));
+        } else if *path == meta && !staged.contains(&asset) && workdir.join(&asset).is_file() {
+            emit(Finding::error(
+                Check::StagedMeta,
+                &meta,
+                format!("staged without its asset, {} isn't staged", asset),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Tip commits of all local branches.
+pub fn branch_tips(repo: &Repository) -> Result<Vec<(String, Commit<'_>)>, CheckError> {
+    let mut tips = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = branch.name()?.unwrap_or("").to_owned();
+        tips.push((name, branch.get().peel_to_commit()?));
+    }
+    Ok(tips)
+}
+
+/// Default size above which blobs added anywhere in the history are reported.
+pub const HISTORY_BLOB_THRESHOLD: usize = 10 * 1024 * 1024;
+
+/// Reports blobs larger than `threshold` bytes added by any commit reachable from a ref, compared
+/// to its first parent. Deleting such a file doesn't shrink clones since the blob stays in the
+/// history, so the check walks every commit and isn't part of [`check_all_with`]. Each blob is
+/// reported once, at the path of the first commit found adding it.
+pub fn test_large_history_objects(
+    config: &CheckConfig,
+    repo: &Repository,
+    emit: &mut Emit,
+) -> Result<(), CheckError> {
+    let threshold = config.history_blob_threshold;
+    info!("checking history for blobs above {} bytes", threshold);
+
+    let mut revwalk = repo.revwalk()?;
+    // every ref, including tags and remote branches, non-commit refs are skipped
+    revwalk.push_glob("*")?;
+    let odb = repo.odb()?;
+
+    let mut seen = HashSet::new();
+    let mut commits = 0;
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        commits += 1;
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if !matches!(delta.status(), Delta::Added | Delta::Modified) {
+                continue;
+            }
+            let file = delta.new_file();
+            // submodules are commits of another repository
+            if file.mode() == FileMode::Commit || !seen.insert(file.id()) {
+                continue;
+            }
+            let (size, _) = odb.read_header(file.id())?;
+            if size > threshold {
+                let path = file.path().unwrap_or_else(|| Path::new(""));
+                emit(Finding::warning(
+                    Check::HistoryBlobs,
+                    path,
+                    format!(
+                        "blob {} of {} bytes added in commit {} is larger than {} bytes and stays in the history even once deleted",
+                        file.id(),
+                        size,
+                        commit.id(),
+                        threshold
+                    ),
+                ));
+            }
+        }
+    }
+    debug!("walked {} commits", commits);
+    Ok(())
+}
+
+const PACKAGE_MANIFEST: &str = "Packages/manifest.json";
+
+/// Whether a dependency version in the package manifest is a semver or a git url. `file:`
+/// paths are local packages, which Unity also accepts.
+fn is_valid_package_version(version: &str) -> bool {
+    static SEMVER: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let semver =
+        SEMVER.get_or_init(|| regex::Regex::new(r"^\d+\.\d+\.\d+(-[a-z0-9.]+)?$").unwrap());
+
+    semver.is_match(version)
+        || [
+            "https://", "http://", "ssh://", "git://", "git+", "git@", "file:",
+        ]
+        .iter()
+        .any(|prefix| version.starts_with(prefix))
+}
+
+/// Reports `GUID:` references of assembly definitions that match no `.asmdef.meta` of the tree,
+/// Unity drops them silently and the referencing assembly fails to compile. References by name
+/// aren't checked, nor are assemblies of packages outside the repository resolvable, so the check
+/// suits projects that keep their assemblies in the tree.
+fn test_asmdef_references(
+    _config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo, root, filter, ..
+    } = *ctx;
+    info!("checking assembly definition references");
+
+    let mut asmdefs = Vec::new();
+    let mut metas = Vec::new();
+    ctx.walk(|dir, entry| {
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = root.join(dir).join(name);
+            if name.ends_with(".asmdef") {
+                asmdefs.push((path, entry.id()));
+            } else if name.ends_with(".asmdef.meta") {
+                metas.push(entry.id());
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    let mut guids = HashSet::new();
+    for id in metas {
+        if let Some(guid) = meta_guid(repo.find_blob(id)?.content()) {
+            guids.insert(guid.to_lowercase());
+        }
+    }
+
+    for (path, id) in asmdefs {
+        if !filter_contains(filter, &path) {
+            continue;
+        }
+        let blob = match read_blob(repo, Check::AsmdefReferences, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        let content = blob.content();
+        let content = content.strip_prefix(b"\xef\xbb\xbf").unwrap_or(content);
+        let asmdef: serde_json::Value = match serde_json::from_slice(content) {
+            Ok(asmdef) => asmdef,
+            Err(e) => {
+                emit(Finding::error(
+                    Check::AsmdefReferences,
+                    path,
+                    format!("invalid json: {}", e),
+                ));
+                continue;
+            }
+        };
+        let references = asmdef
+            .get("references")
+            .and_then(|references| references.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|reference| reference.as_str());
+        for reference in references {
+            let guid = match reference.strip_prefix("GUID:") {
+                Some(guid) => guid,
+                None => continue,
+            };
+            trace!(
+                "[asmdef-references] {}: references {}",
+                path.display(),
+                guid
+            );
+            if !guids.contains(&guid.to_lowercase()) {
+                emit(Finding::error(
+                    Check::AsmdefReferences,
+                    &path,
+                    format!("references missing assembly definition GUID:{}", guid),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn test_package_manifest(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking package manifest");
+
+    let path = Path::new(PACKAGE_MANIFEST);
+    let entry = match path
+        .strip_prefix(root)
+        .map(|relative| tree.get_path(relative))
+    {
+        Ok(Ok(entry)) => entry,
+        // the manifest is outside of the checked subtree
+        Err(_) => return Ok(()),
+        Ok(Err(e)) if e.code() == ErrorCode::NotFound => return Ok(()),
+        Ok(Err(e)) => return Err(e.into()),
+    };
+    if !filter_contains(filter, path) {
+        return Ok(());
+    }
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+
+    let manifest: serde_json::Value = match serde_json::from_slice(blob.content()) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            emit(Finding::error(
+                Check::Manifest,
+                path,
+                format!("invalid json: {}", e),
+            ));
+            return Ok(());
+        }
+    };
+
+    let dependencies = match manifest.get("dependencies") {
+        Some(serde_json::Value::Object(dependencies)) => dependencies,
+        Some(_) => {
+            emit(Finding::error(
+                Check::Manifest,
+                path,
+                "\"dependencies\" is not an object".into(),
+            ));
+            return Ok(());
+        }
+        None => {
+            emit(Finding::error(
+                Check::Manifest,
+                path,
+                "missing \"dependencies\" object".into(),
+            ));
+            return Ok(());
+        }
+    };
+
+    for (name, version) in dependencies {
+        match version.as_str() {
+            Some(version) if is_valid_package_version(version) => {}
+            _ => emit(Finding::error(
+                Check::Manifest,
+                path,
+                format!("invalid version for {}: {}", name, version),
+            )),
+        }
+    }
+    Ok(())
+}
+
+/// Project settings read from `.checklfs.toml` at the root of the checked tree, so that each
+/// commit is checked against the settings it was committed with.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct Config {
+    structure: StructureConfig,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct StructureConfig {
+    /// Directories required in addition to [`REQUIRED_DIRECTORIES`], e.g. `Assets/Scripts`.
+    required: Vec<String>,
+}
+
+const CONFIG_PATH: &str = ".checklfs.toml";
+
+fn load_config(repo: &Repository, tree: &Tree) -> Result<Config> {
+    let entry = match tree.get_path(Path::new(CONFIG_PATH)) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    let content = std::str::from_utf8(blob.content())?;
+    toml::from_str(content).map_err(|e| anyhow::anyhow!("invalid {}: {}", CONFIG_PATH, e))
+}
+
+/// Directories every Unity project has at its root.
+const REQUIRED_DIRECTORIES: &[&str] = &["Assets", "ProjectSettings", "Packages"];
+
+/// Reports missing [`REQUIRED_DIRECTORIES`] and the directories listed under
+/// `[structure] required` in `.checklfs.toml`.
+fn test_assets_structure(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking project structure");
+
+    // the layout is only defined relative to the repository root
+    if root != Path::new("") {
+        debug!(
+            "skipping project structure check for subtree {}",
+            root.display()
+        );
+        return Ok(());
+    }
+
+    let config = load_config(repo, tree)?;
+
+    let required = REQUIRED_DIRECTORIES
+        .iter()
+        .map(|dir| dir.to_string())
+        .chain(
+            config
+                .structure
+                .required
+                .iter()
+                .map(|dir| dir.trim_end_matches('/').to_owned()),
+        );
+
+    for dir in required {
+        let path = PathBuf::from(&dir);
+        // in incremental modes only report directories whose content changed
+        if !filter_descends(filter, &path) {
+            continue;
+        }
+        let exists = match tree.get_path(&path) {
+            Ok(entry) => entry.kind() == Some(ObjectType::Tree),
+            Err(e) if e.code() == ErrorCode::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        if !exists {
+            emit(Finding::error(
+                Check::AssetsStructure,
+                path,
+                "missing required directory".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Default number of files sharing a basename before they are reported.
+pub const DUPLICATE_BASENAME_MIN: usize = 2;
+
+/// Reports files under the asset roots sharing a basename (case-insensitively) with files in other
+/// directories, e.g. two `Player.prefab`, when at least `min_count` files share it. Metas are
+/// not counted since they mirror their asset, nor are the files the meta check ignores.
+fn test_duplicate_basenames(
+    config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext { root, filter, .. } = *ctx;
+    let min_count = config.duplicate_basename_min;
+    let asset_roots = &config.asset_roots;
+    let ignore_fn = &*config.meta_ignore;
+    info!("checking duplicate basenames");
+
+    let mut basenames: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    ctx.walk(|dir, entry| {
+        let path = root.join(dir).join(entry.name().unwrap_or(""));
+        if filter_excludes(filter, &path, entry.kind() == Some(ObjectType::Tree)) {
+            return TreeWalkResult::Skip;
+        }
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            if !name.ends_with(".meta") && !ignore_fn(name) && asset_roots.contains(&path) {
+                basenames.entry(name.to_lowercase()).or_default().push(path);
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for paths in basenames.values() {
+        if paths.len() < min_count.max(2) {
+            continue;
+        }
+        for path in paths {
+            if !filter_contains(filter, path) {
+                continue;
+            }
+            let others = paths
+                .iter()
+                .filter(|other| *other != path)
+                .map(|other| other.display().to_string())
+                .collect::<Vec<_>>();
+            emit(Finding::error(
+                Check::DuplicateBasenames,
+                path,
+                format!("basename is also used by {}", others.join(", ")),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// File names created by operating systems (Finder, Explorer, Spotlight) which should never be
+/// committed.
+const OS_METADATA_NAMES: &[&str] = &[
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+    ".Spotlight-V100",
+    ".Trashes",
+];
+
+fn is_os_metadata(name: &str) -> bool {
+    // `._*` files are macOS resource forks
+    OS_METADATA_NAMES.contains(&name) || name.starts_with("._")
+}
+
+fn test_os_metadata(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking os metadata files");
+
+    iter_tree_os_metadata(repo, root, tree, filter, emit)?;
+    Ok(())
+}
+
+/// Names of files that are empty on purpose, e.g. to keep an otherwise empty directory.
+const EMPTY_FILE_ALLOWLIST: &[&str] = &[".gitkeep", ".keep", ".gitignore", ".nomedia"];
+
+/// Reports zero-byte files, usually left by a failed export, which Unity fails to import.
+fn test_empty_files(_config: &CheckConfig, ctx: &CheckContext, emit: &mut Emit) -> Result<()> {
+    let CheckContext {
+        repo, root, filter, ..
+    } = *ctx;
+    info!("checking empty files");
+
+    let mut blobs = Vec::new();
+    ctx.walk(|dir, entry| {
+        if entry.kind() == Some(ObjectType::Tree) {
+            let path = root.join(dir).join(entry.name().unwrap_or(""));
+            if !filter_descends(filter, &path) {
+                return TreeWalkResult::Skip;
+            }
+        }
+        if let (Some(name), Some(ObjectType::Blob)) = (entry.name(), entry.kind()) {
+            let path = root.join(dir).join(name);
+            if !EMPTY_FILE_ALLOWLIST.contains(&name) && filter_contains(filter, &path) {
+                blobs.push((path, entry.id()));
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for (path, id) in blobs {
+        let blob = match read_blob(repo, Check::EmptyFiles, &path, id, emit) {
+            Some(blob) => blob,
+            None => continue,
+        };
+        if blob.size() == 0 {
+            emit(Finding::error(
+                Check::EmptyFiles,
+                path,
+                "empty file, likely a failed export or save".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The `.gitignore` files of a tree by directory, patterns of each are relative to its directory.
+struct TreeGitignores(HashMap<PathBuf, ignore::gitignore::Gitignore>);
+
+impl TreeGitignores {
+    /// Reads every `.gitignore` of `tree`, the tree checked at `root`. Invalid patterns are
+    /// skipped with a warning, like git does.
+    fn from_tree(repo: &Repository, tree: &Tree, root: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.name() == Some(".gitignore") && entry.kind() == Some(ObjectType::Blob) {
+                files.push((root.join(dir), entry.id()));
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        let mut gitignores = HashMap::new();
+        for (dir, id) in files {
+            let blob = repo.find_blob(id)?;
+            let source = dir.join(".gitignore");
+            let mut builder = ignore::gitignore::GitignoreBuilder::new(&dir);
+            for line in String::from_utf8_lossy(blob.content()).lines() {
+                if let Err(e) = builder.add_line(Some(source.clone()), line) {
+                    warn!(
+                        "{}: ignoring invalid pattern {:?}: {}",
+                        source.display(),
+                        line,
+                        e
+                    );
+                }
+            }
+            gitignores.insert(dir, builder.build()?);
+        }
+        Ok(TreeGitignores(gitignores))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The pattern ignoring `path`, the deepest .gitignore with an opinion decides, like git.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<&ignore::gitignore::Glob> {
+        path.ancestors()
+            .skip(1)
+            .filter_map(|dir| self.0.get(dir))
+            .map(|gitignore| gitignore.matched(path, is_dir))
+            .find(|m| !m.is_none())
+            .and_then(|m| m.inner().filter(|_| m.is_ignore()).copied())
+    }
+}
+
+/// Reports committed files and directories matching the `.gitignore` files of the tree, usually
+/// committed before the pattern was added since git doesn't untrack them afterwards. Ignored
+/// directories are reported once instead of once per file.
+fn test_gitignore_coverage(
+    _config: &CheckConfig,
+    ctx: &CheckContext,
+    emit: &mut Emit,
+) -> Result<()> {
+    let CheckContext {
+        repo,
+        tree,
+        root,
+        filter,
+        ..
+    } = *ctx;
+    info!("checking committed files matching .gitignore");
+
+    let gitignores = TreeGitignores::from_tree(repo, tree, root)?;
+    if gitignores.is_empty() {
+        return Ok(());
+    }
+
+    ctx.walk(|dir, entry| {
+        let name = match entry.name() {
+            Some(name) => name,
+            None => return TreeWalkResult::Ok,
+        };
+        let path = root.join(dir).join(name);
+        let is_dir = entry.kind() == Some(ObjectType::Tree);
+
+        let glob = match gitignores.matched(&path, is_dir) {
+            Some(glob) => glob,
+            None if is_dir && !filter_descends(filter, &path) => return TreeWalkResult::Skip,
+            None => return TreeWalkResult::Ok,
+        };
+        trace!(
+            "[gitignore] {}: matches {:?}",
+            path.display(),
+            glob.original()
+        );
+
+        if filter_contains(filter, &path) {
+            let source = glob
+                .from()
+                .map(|source| source.display().to_string())
+                .unwrap_or_default();
+            emit(Finding::error(
+                Check::Gitignore,
+                &path,
+                format!(
+                    "committed but matches {:?} in {}, untrack it with `git rm {}--cached`",
+                    glob.original(),
+                    source,
+                    if is_dir { "-r " } else { "" }
+                ),
+            ));
+        }
+        if is_dir {
+            TreeWalkResult::Skip
+        } else {
+            TreeWalkResult::Ok
+        }
+    })?;
+    Ok(())
+}
+
+fn iter_tree_os_metadata(
+    repo: &Repository,
+    prefix: &Path,
+    tree: &Tree,
+    filter: Option<&PathFilter>,
+    emit: &mut Emit,
+) -> Result<()> {
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            None => continue,
+            Some(name) => name,
+        };
+        let path = prefix.join(name);
+
+        if is_os_metadata(name) {
+            if !filter_contains(filter, &path) {
+                continue;
+            }
+            emit(Finding::error(
+                Check::OsMetadata,
+                path,
+                format!("os metadata file, consider adding {:?} to .gitignore", name),
+            ));
+            continue;
+        }
+
+        if entry.kind() == Some(ObjectType::Tree) && filter_descends(filter, &path) {
+            let obj = entry.to_object(repo)?;
+            let tree = obj.peel_to_tree()?;
+            iter_tree_os_metadata(repo, &path, &tree, filter, emit)?;
+        }
+    }
+    Ok(())
+}
+
+/// Settings of the checks run by [`check_all_with`].
+#[derive(Clone)]
+pub struct CheckConfig {
+    /// Checks to run, findings of other checks are dropped.
+    pub checks: HashSet<Check>,
+    /// Directory of the tree to check, paths stay relative to the repository root.
+    pub subtree: Option<PathBuf>,
+    pub case_scope: CaseScope,
+    pub attr_source: AttrSource,
+    pub verify_lfs_size: bool,
+    /// Warns about pointers whose object isn't in the local lfs object store, implied by
+    /// `verify_lfs_size`.
+    pub verify_lfs_reachability: bool,
+    /// Directories in which every entry needs a meta.
+    pub asset_roots: AssetRoots,
+    /// gitignore-style globs of paths that aren't walked nor reported, e.g. vendored `ThirdParty/`.
+    pub exclude: Vec<String>,
+    /// File names the meta check ignores, [`test_meta_ignore`] by default.
+    pub meta_ignore: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+    /// Whether the meta check skips paths ignored by the `.gitignore` files of the tree.
+    pub respect_gitignore: bool,
+    /// Runs the checks one after another on the calling thread, lfs blobs included, so their logs
+    /// don't interleave.
+    pub sequential: bool,
+    /// Expected importer by lowercase extension, see [`importer_map`].
+    pub importers: HashMap<String, String>,
+    pub duplicate_basename_min: usize,
+    /// Size from which a blob tracked by lfs that isn't a pointer is reported.
+    pub lfs_threshold: usize,
+    pub texture_lfs_threshold: usize,
+    pub audio_lfs_threshold: usize,
+    pub video_lfs_threshold: usize,
+    pub scene_lfs_threshold: usize,
+    pub shader_lfs_threshold: usize,
+    pub resources_threshold: usize,
+    pub streaming_assets_lfs_threshold: usize,
+    pub history_blob_threshold: usize,
+    pub max_component_length: usize,
+    /// Extensions reported by the lfs-text check, see [`LFS_TEXT_EXTENSIONS`].
+    pub lfs_text_extensions: Vec<String>,
+    /// Severity replacing the one reported by the check.
+    pub severities: HashMap<Check, Severity>,
+    /// Object directories added to every repository handle the checks open, e.g. the quarantine
+    /// holding the pushed objects in a pre-receive hook.
+    pub object_dirs: Vec<PathBuf>,
+}
+
+impl Default for CheckConfig {
+    fn default() -> Self {
+        CheckConfig {
+            checks: Check::ALL
+                .iter()
+                .copied()
+                .filter(|check| check.default_enabled())
+                .collect(),
+            subtree: None,
+            case_scope: CaseScope::Global,
+            attr_source: AttrSource::Index,
+            verify_lfs_size: false,
+            verify_lfs_reachability: false,
+            asset_roots: AssetRoots::default(),
+            exclude: Vec::new(),
+            meta_ignore: Arc::new(test_meta_ignore),
+            respect_gitignore: false,
+            sequential: false,
+            importers: DEFAULT_IMPORTERS
+                .iter()
+                .map(|(ext, importer)| (ext.to_string(), importer.to_string()))
+                .collect(),
+            duplicate_basename_min: DUPLICATE_BASENAME_MIN,
+            lfs_threshold: LFS_THRESHOLD,
+            texture_lfs_threshold: TEXTURE_LFS_THRESHOLD,
+            audio_lfs_threshold: AUDIO_LFS_THRESHOLD,
+            video_lfs_threshold: VIDEO_LFS_THRESHOLD,
+            scene_lfs_threshold: SCENE_LFS_THRESHOLD,
+            shader_lfs_threshold: SHADER_LFS_THRESHOLD,
+            resources_threshold: RESOURCES_THRESHOLD,
+            streaming_assets_lfs_threshold: STREAMING_ASSETS_LFS_THRESHOLD,
+            history_blob_threshold: HISTORY_BLOB_THRESHOLD,
+            max_component_length: MAX_COMPONENT_LENGTH,
+            lfs_text_extensions: LFS_TEXT_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            severities: HashMap::new(),
+            object_dirs: Vec::new(),
+        }
+    }
+}
+
+/// Builder methods for library users, starting from the defaults of the command line, e.g.
+/// `CheckConfig::new().with_assets_root("Assets/").with_lfs_threshold(10 * 1024 * 1024)
+/// .with_ignore_pattern("*.tmp").build()`.
+impl CheckConfig {
+    pub fn new() -> Self {
+        CheckConfig::default()
+    }
+
+    /// Replaces the severity of the finding if it is overridden in [`CheckConfig::severities`].
+    pub fn apply_severity(&self, finding: &mut Finding) {
+        if let Some(&severity) = self.severities.get(&finding.check) {
+            finding.severity = severity;
+        }
+    }
+
+    /// Runs only the given checks.
+    pub fn with_checks(mut self, checks: impl IntoIterator<Item = Check>) -> Self {
+        self.checks = checks.into_iter().collect();
+        self
+    }
+
+    /// Runs the check in addition to the selected ones.
+    pub fn with_check(mut self, check: Check) -> Self {
+        self.checks.insert(check);
+        self
+    }
+
+    pub fn with_subtree(mut self, subtree: impl Into<PathBuf>) -> Self {
+        self.subtree = Some(subtree.into());
+        self
+    }
+
+    pub fn with_case_scope(mut self, case_scope: CaseScope) -> Self {
+        self.case_scope = case_scope;
+        self
+    }
+
+    pub fn with_attr_source(mut self, attr_source: AttrSource) -> Self {
+        self.attr_source = attr_source;
+        self
+    }
+
+    pub fn with_verify_lfs_size(mut self, verify: bool) -> Self {
+        self.verify_lfs_size = verify;
+        self
+    }
+
+    pub fn with_verify_lfs_reachability(mut self, verify: bool) -> Self {
+        self.verify_lfs_reachability = verify;
+        self
+    }
+
+    /// Adds an asset root, the first one replaces the default `Assets`.
+    pub fn with_assets_root(mut self, root: impl AsRef<str>) -> Self {
+        self.asset_roots.push(root.as_ref());
+        self
+    }
+
+    /// Adds a gitignore-style glob of paths that aren't walked nor reported.
+    pub fn with_ignore_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    pub fn with_meta_ignore(
+        mut self,
+        ignore: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.meta_ignore = Arc::new(ignore);
+        self
+    }
+
+    pub fn with_respect_gitignore(mut self, respect: bool) -> Self {
+        self.respect_gitignore = respect;
+        self
+    }
+
+    pub fn with_sequential(mut self, sequential: bool) -> Self {
+        self.sequential = sequential;
+        self
+    }
+
+    /// Expects `importer` in the metas of files with the extension `ext`, which is matched
+    /// case-insensitively and may start with a dot like on the command line.
+    pub fn with_importer(mut self, ext: impl AsRef<str>, importer: impl Into<String>) -> Self {
+        self.importers
+            .insert(importer_extension(ext.as_ref()), importer.into());
+        self
+    }
+
+    pub fn with_duplicate_basename_min(mut self, min: usize) -> Self {
+        self.duplicate_basename_min = min;
+        self
+    }
+
+    /// Sets the size from which files tracked by LFS must be pointers, see
+    /// [`CheckConfig::lfs_threshold`].
+    pub fn with_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.lfs_threshold = threshold;
+        self
+    }
+
+    /// Sets the size above which textures, audio, video, scenes, shaders and StreamingAssets
+    /// files must be in LFS, the individual `with_*_lfs_threshold` override it. Files tracked by
+    /// LFS are checked against [`CheckConfig::with_lfs_threshold`] instead.
+    pub fn with_large_file_threshold(self, threshold: usize) -> Self {
+        self.with_texture_lfs_threshold(threshold)
+            .with_audio_lfs_threshold(threshold)
+            .with_video_lfs_threshold(threshold)
+            .with_scene_lfs_threshold(threshold)
+            .with_shader_lfs_threshold(threshold)
+            .with_streaming_assets_lfs_threshold(threshold)
+    }
+
+    pub fn with_texture_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.texture_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_audio_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.audio_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_video_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.video_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_scene_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.scene_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_shader_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.shader_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_streaming_assets_lfs_threshold(mut self, threshold: usize) -> Self {
+        self.streaming_assets_lfs_threshold = threshold;
+        self
+    }
+
+    pub fn with_resources_threshold(mut self, threshold: usize) -> Self {
+        self.resources_threshold = threshold;
+        self
+    }
+
+    pub fn with_history_blob_threshold(mut self, threshold: usize) -> Self {
+        self.history_blob_threshold = threshold;
+        self
+    }
+
+    pub fn with_max_component_length(mut self, max_length: usize) -> Self {
+        self.max_component_length = max_length;
+        self
+    }
+
+    pub fn with_lfs_text_extensions(
+        mut self,
+        extensions: impl IntoIterator<Item = String>,
+    ) -> Self {
+        self.lfs_text_extensions = extensions.into_iter().collect();
+        self
+    }
+
+    /// Reports findings of the check with `severity` instead of its own.
+    pub fn with_severity(mut self, check: Check, severity: Severity) -> Self {
+        self.severities.insert(check, severity);
+        self
+    }
+
+    /// Ends a chain of builder methods. Every step already yields a usable config, so this
+    /// returns it as is.
+    pub fn build(self) -> Self {
+        self
+    }
+}
+
+/// The tree a check runs against, with a repository handle of the check's own.
+#[derive(Clone, Copy)]
+pub struct CheckContext<'a> {
+    pub repo: &'a Repository,
+    pub tree: &'a Tree<'a>,
+    /// Path of `tree` in the repository, empty unless a subtree is checked.
+    pub root: &'a Path,
+    /// Paths to report, `None` for all of them.
+    pub filter: Option<&'a PathFilter>,
+    /// Entries of `tree` recorded by a shared walk, `None` to have each check walk the tree.
+    pub entries: Option<&'a TreeEntries>,
+}
+
+impl CheckContext<'_> {
+    /// Walks `tree` in pre-order like [`Tree::walk`], replaying the shared walk if there is one.
+    /// Aborting a replayed walk stops it without an error.
+    pub fn walk<F>(&self, mut visit: F) -> Result<(), git2::Error>
+    where
+        F: FnMut(&str, &WalkEntry) -> TreeWalkResult,
+    {
+        match self.entries {
+            Some(entries) => {
+                entries.walk(visit);
+                Ok(())
+            }
+            None => self.tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+                let entry = WalkEntry {
+                    name: entry.name_bytes(),
+                    id: entry.id(),
+                    kind: entry.kind(),
+                };
+                visit(dir, &entry)
+            }),
+        }
+    }
+}
+
+/// An entry visited by [`CheckContext::walk`].
+pub struct WalkEntry<'a> {
+    name: &'a [u8],
+    id: Oid,
+    kind: Option<ObjectType>,
+}
+
+impl WalkEntry<'_> {
+    /// The name, `None` unless it is valid utf-8.
+    pub fn name(&self) -> Option<&str> {
+        std::str::from_utf8(self.name).ok()
+    }
+
+    pub fn name_bytes(&self) -> &[u8] {
+        self.name
+    }
+
+    pub fn id(&self) -> Oid {
+        self.id
+    }
+
+    pub fn kind(&self) -> Option<ObjectType> {
+        self.kind
+    }
+
+    /// Whether the entry is a gitlink, see [`is_submodule`].
+    pub fn is_submodule(&self) -> bool {
+        self.kind == Some(ObjectType::Commit)
+    }
+}
+
+/// How the checks run by [`check_tree_with`] read the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkStrategy {
+    /// Every check walks the tree on its own, reading each subtree again.
+    PerCheck,
+    /// The tree is walked once up front and every check replays the recorded entries.
+    Shared,
+}
+
+/// Every entry of a tree and its subtrees in pre-order, recorded once so that several checks can
+/// walk them without reading the trees again.
+pub struct TreeEntries {
+    entries: Vec<RecordedEntry>,
+}
+
+struct RecordedEntry {
+    dir: String,
+    name: Vec<u8>,
+    id: Oid,
+    kind: Option<ObjectType>,
+    /// Index of the entry after the last one below this one, where a skip continues.
+    end: usize,
+}
+
+impl TreeEntries {
+    pub fn new(repo: &Repository, tree: &Tree) -> Result<Self, git2::Error> {
+        let mut entries = Vec::new();
+        Self::record(repo, "", tree, &mut entries)?;
+        Ok(TreeEntries { entries })
+    }
+
+    fn record(
+        repo: &Repository,
+        dir: &str,
+        tree: &Tree,
+        entries: &mut Vec<RecordedEntry>,
+    ) -> Result<(), git2::Error> {
+        for entry in tree.iter() {
+            let index = entries.len();
+            entries.push(RecordedEntry {
+                dir: dir.to_owned(),
+                name: entry.name_bytes().to_owned(),
+                id: entry.id(),
+                kind: entry.kind(),
+                end: index + 1,
+            });
+            if entry.kind() == Some(ObjectType::Tree) {
+                let subtree = repo.find_tree(entry.id())?;
+                let dir = format!("{}{}/", dir, String::from_utf8_lossy(entry.name_bytes()));
+                Self::record(repo, &dir, &subtree, entries)?;
+                entries[index].end = entries.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn walk<F>(&self, mut visit: F)
+    where
+        F: FnMut(&str, &WalkEntry) -> TreeWalkResult,
+    {
+        let mut index = 0;
+        while let Some(recorded) = self.entries.get(index) {
+            let entry = WalkEntry {
+                name: &recorded.name,
+                id: recorded.id,
+                kind: recorded.kind,
+            };
+            index = match visit(&recorded.dir, &entry) {
+                TreeWalkResult::Ok => index + 1,
+                TreeWalkResult::Skip => recorded.end,
+                TreeWalkResult::Abort => break,
+            };
+        }
+    }
+}
+
+type CheckFn = fn(&CheckConfig, &CheckContext, &mut Emit) -> Result<()>;
+
+/// The checks [`check_all_with`] runs against the tree, in the order they are started.
+const TREE_CHECKS: &[(Check, CheckFn)] = &[
+    (Check::Meta, |config, ctx, emit| {
+        Ok(test_meta(config, ctx, emit)?)
+    }),
+    (Check::Case, test_case),
+    (Check::Lfs, test_lfs),
+    (Check::OsMetadata, test_os_metadata),
+    (Check::Manifest, test_package_manifest),
+    (Check::Importer, test_importer),
+    (Check::DuplicateBasenames, test_duplicate_basenames),
+    (Check::Fbx, test_fbx_lfs),
+    (Check::Texture, test_texture_lfs),
+    (Check::Audio, test_audio_lfs),
+    (Check::Video, test_video_lfs),
+    (Check::AssetsStructure, test_assets_structure),
+    (Check::Scene, test_scene_lfs),
+    (Check::Shader, test_shader_lfs),
+    (Check::MetaLineEndings, test_meta_line_endings),
+    (Check::MetaVersion, test_meta_version),
+    (Check::LfsText, test_lfs_text),
+    (Check::LfsLocks, test_lfs_locks),
+    (Check::Binary, test_binary_text_mismatch),
+    (Check::Gitignore, test_gitignore_coverage),
+    (Check::EmptyFiles, test_empty_files),
+    (Check::LfsAttributes, test_lfs_attributes),
+    (Check::MetaCase, test_meta_case),
+    (Check::Resources, test_resources_folder),
+    (Check::StreamingAssets, test_streaming_assets),
+    (Check::EditorFolderMeta, test_editor_folder_meta),
+    (Check::Submodules, test_gitmodules_safety),
+    (Check::UnityBinary, test_binary_unity_assets),
+    (Check::ExtensionCase, test_extension_case),
+    (Check::AsmdefReferences, test_asmdef_references),
+    (Check::ComponentLength, test_component_length),
+];
+
+/// What every check of a [`check_all_with`] call is run against, shared by their threads.
+struct CheckTarget {
+    config: CheckConfig,
+    path: PathBuf,
+    tree_id: Oid,
+    root: PathBuf,
+    filter: Option<Arc<PathFilter>>,
+}
+
+/// When a check run by [`check_all_with`] started and how long it took.
+#[derive(Debug, Clone, Copy)]
+pub struct CheckTiming {
+    pub check: Check,
+    pub start: SystemTime,
+    pub elapsed: Duration,
+}
+
+/// A check running on its own thread, or already run on the calling thread.
+enum CheckRun {
+    Thread(std::thread::JoinHandle<Result<CheckTiming>>),
+    Done(Result<CheckTiming>),
+}
+
+impl CheckRun {
+    fn join(self) -> Result<CheckTiming> {
+        match self {
+            CheckRun::Thread(thread) => thread.join().unwrap(),
+            CheckRun::Done(result) => result,
+        }
+    }
+}
+
+/// Runs a check on its own thread, or right away with `sequential`, sending its findings as they
+/// are found and returning when it started and how long it took.
+fn spawn_check(
+    check: Check,
+    f: CheckFn,
+    target: &Arc<CheckTarget>,
+    sender: &mpsc::Sender<Finding>,
+) -> CheckRun {
+    let target = target.clone();
+    let sender = sender.clone();
+    let sequential = target.config.sequential;
+    let run = move || {
+        let start = SystemTime::now();
+        let started = Instant::now();
+        let repo = open_repository(&target.path, &target.config.object_dirs)?;
+        let tree = repo.find_tree(target.tree_id)?;
+        let ctx = CheckContext {
+            repo: &repo,
+            tree: &tree,
+            root: &target.root,
+            filter: target.filter.as_deref(),
+            entries: None,
+        };
+        // the receiver only goes away once every check has finished
+        f(&target.config, &ctx, &mut |finding| {
+            let _ = sender.send(finding);
+        })?;
+        Ok(CheckTiming {
+            check,
+            start,
+            elapsed: started.elapsed(),
+        })
+    };
+    if sequential {
+        CheckRun::Done(run())
+    } else {
+        CheckRun::Thread(std::thread::spawn(run))
+    }
+}
+
+/// The tree to check with its path and the paths to report, after applying
+/// [`CheckConfig::subtree`] and [`CheckConfig::exclude`].
+fn check_target(
+    config: &CheckConfig,
+    repo: &Repository,
+    tree_id: Oid,
+    filter: Option<Arc<PathFilter>>,
+) -> Result<(Oid, PathBuf, Option<Arc<PathFilter>>), CheckError> {
+    let (tree_id, root) = match &config.subtree {
+        Some(subtree) => {
+            let entry = repo.find_tree(tree_id)?.get_path(subtree)?;
+            if entry.kind() != Some(ObjectType::Tree) {
+                return Err(CheckError::Other(anyhow::anyhow!(
+                    "{} is not a directory",
+                    subtree.display()
+                )));
+            }
+            (entry.id(), subtree.clone())
+        }
+        None => (tree_id, PathBuf::new()),
+    };
+    let filter = if config.exclude.is_empty() {
+        filter
+    } else {
+        let mut builder = ignore::gitignore::GitignoreBuilder::new("");
+        for glob in &config.exclude {
+            builder.add_line(None, glob)?;
+        }
+        let mut filter = filter.map(|filter| (*filter).clone()).unwrap_or_default();
+        filter.exclude = Some(builder.build()?);
+        Some(Arc::new(filter))
+    };
+    Ok((tree_id, root, filter))
+}
+
+/// Whether `check` is run by [`check_all_with`], the lfs walk also finds the invalid oids.
+fn is_tree_check_enabled(checks: &HashSet<Check>, check: Check) -> bool {
+    checks.contains(&check) || (check == Check::Lfs && checks.contains(&Check::LfsInvalidOid))
+}
+
+/// Runs the enabled checks against a tree in parallel, each check opening its own repository
+/// handle, and calls `emit` on the calling thread for every finding as soon as it is found. The
+/// order of findings across checks is unspecified, unless [`CheckConfig::sequential`] runs the
+/// checks one after another on the calling thread and findings are passed once all are done.
+/// `filter` limits reporting to the given paths. Returns when each check started and how long it
+/// took.
+pub fn check_all_with<F>(
+    config: &CheckConfig,
+    repo: &Repository,
+    tree_id: Oid,
+    filter: Option<Arc<PathFilter>>,
+    mut emit: F,
+) -> Result<Vec<CheckTiming>, CheckError>
+where
+    F: FnMut(Finding),
+{
+    let path = repo.workdir().unwrap_or(repo.path()).to_owned();
+    let (tree_id, root, filter) = check_target(config, repo, tree_id, filter)?;
+
+    if log_enabled!(Level::Debug) {
+        let tree = repo.find_tree(tree_id)?;
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if is_submodule(entry) {
+                debug!("skipping submodule {}{}", dir, entry.name().unwrap_or(""));
+            }
+            TreeWalkResult::Ok
+        })?;
+    }
+    let checks = &config.checks;
+    let (sender, receiver) = mpsc::channel();
+    let target = Arc::new(CheckTarget {
+        config: config.clone(),
+        path,
+        tree_id,
+        root,
+        filter,
+    });
+    let mut threads = Vec::new();
+    for &(check, f) in TREE_CHECKS {
+        if is_tree_check_enabled(checks, check) {
+            threads.push(spawn_check(check, f, &target, &sender));
+        }
+    }
+
+    drop(sender);
+
+    for mut finding in receiver {
+        if checks.contains(&finding.check) {
+            config.apply_severity(&mut finding);
+            emit(finding);
+        }
+    }
+
+    let mut timings = Vec::new();
+    for thread in threads {
+        timings.push(thread.join()?);
+    }
+    Ok(timings)
+}
+
+/// Runs the enabled tree checks one after another on the calling thread with the given handle,
+/// which unlike with [`check_all_with`] may be an in-memory repository, reading the tree as
+/// `strategy` says. `emit` is called for every finding as soon as it is found.
+pub fn check_tree_with<F>(
+    config: &CheckConfig,
+    repo: &Repository,
+    tree_id: Oid,
+    filter: Option<Arc<PathFilter>>,
+    strategy: WalkStrategy,
+    mut emit: F,
+) -> Result<Vec<CheckTiming>, CheckError>
+where
+    F: FnMut(Finding),
+{
+    let (tree_id, root, filter) = check_target(config, repo, tree_id, filter)?;
+    // the lfs check opens handles of its own by path unless it runs sequentially
+    let config = CheckConfig {
+        sequential: true,
+        ..config.clone()
+    };
+    let tree = repo.find_tree(tree_id)?;
+    let entries = match strategy {
+        WalkStrategy::PerCheck => None,
+        WalkStrategy::Shared => Some(TreeEntries::new(repo, &tree)?),
+    };
+    let ctx = CheckContext {
+        repo,
+        tree: &tree,
+        root: &root,
+        filter: filter.as_deref(),
+        entries: entries.as_ref(),
+    };
+
+    let mut timings = Vec::new();
+    for &(check, f) in TREE_CHECKS {
+        if !is_tree_check_enabled(&config.checks, check) {
+            continue;
+        }
+        let start = SystemTime::now();
+        let started = Instant::now();
+        f(&config, &ctx, &mut |mut finding| {
+            if config.checks.contains(&finding.check) {
+                config.apply_severity(&mut finding);
+                emit(finding);
+            }
+        })?;
+        timings.push(CheckTiming {
+            check,
+            start,
+            elapsed: started.elapsed(),
+        });
+    }
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTER: &[u8] = b"version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize 12345\n";
+
+    /// Writes `files` into the working directory of `repo`.
+    fn write_files(repo: &Repository, files: &[(&str, &[u8])]) {
+        for (path, content) in files {
+            let path = repo.workdir().unwrap().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    /// Stages `files` in a new repository and returns the tree of the index.
+    fn repo_with_files(files: &[(&str, &[u8])]) -> (tempfile::TempDir, Repository, Oid) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        write_files(&repo, files);
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], IndexAddOption::DEFAULT | IndexAddOption::FORCE, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        (dir, repo, tree_id)
+    }
+
+    fn findings(config: &CheckConfig, repo: &Repository, tree_id: Oid) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        check_all_with(config, repo, tree_id, None, |finding| {
+            findings.push(finding)
+        })
+        .unwrap();
+        findings
+    }
+
+    /// Paths and messages of the findings on a tree of `files`, sorted by path.
+    fn tree_findings(config: &CheckConfig, files: &[(&str, &[u8])]) -> Vec<(String, String)> {
+        let (_dir, repo, tree_id) = repo_with_files(files);
+        let mut findings = findings(config, &repo, tree_id)
+            .into_iter()
+            .map(|finding| (finding.path.display().to_string(), finding.message))
+            .collect::<Vec<_>>();
+        findings.sort();
+        findings
+    }
+
+    /// Stages every file of the working directory, deletions included, and commits them on HEAD.
+    fn commit_all<'r>(repo: &'r Repository, message: &str) -> Commit<'r> {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], IndexAddOption::DEFAULT | IndexAddOption::FORCE, None)
+            .unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents = parent.iter().collect::<Vec<_>>();
+        let id = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        repo.find_commit(id).unwrap()
+    }
+
+    const META: &[u8] = b"fileFormatVersion: 2\n";
+
+    fn meta(guid: &str) -> Vec<u8> {
+        format!("fileFormatVersion: 2\nguid: {}\n", guid).into_bytes()
+    }
+
+    #[test]
+    fn lfs_reports_unreadable_blob_and_keeps_walking() {
+        let (dir, repo, tree_id) = repo_with_files(&[
+            (".gitattributes", b"*.png merge=lfs filter=lfs -text\n"),
+            ("Assets/Broken.png", POINTER),
+            ("Assets/Broken.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/Big.png", &[0; 200]),
+            ("Assets/Big.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/NoMeta.txt", b"text\n"),
+        ]);
+        let id = repo
+            .find_tree(tree_id)
+            .unwrap()
+            .get_path(Path::new("Assets/Broken.png"))
+            .unwrap()
+            .id()
+            .to_string();
+        std::fs::remove_file(
+            dir.path()
+                .join(".git/objects")
+                .join(&id[..2])
+                .join(&id[2..]),
+        )
+        .unwrap();
+
+        let config = CheckConfig::new().with_checks([Check::Lfs, Check::Meta]);
+        let findings = findings(&config, &repo, tree_id);
+        let unreadable = findings
+            .iter()
+            .find(|f| f.path == Path::new("Assets/Broken.png"))
+            .unwrap();
+        assert_eq!(unreadable.check, Check::Lfs);
+        assert_eq!(unreadable.severity, Severity::Error);
+        assert!(unreadable.message.starts_with("can't read object"));
+        assert_ne!(unreadable.check.exit_code(), 0);
+        // the other blobs and checks aren't cut short
+        assert!(findings
+            .iter()
+            .any(|f| f.check == Check::Lfs && f.path == Path::new("Assets/Big.png")));
+        assert!(findings
+            .iter()
+            .any(|f| f.check == Check::Meta && f.path == Path::new("Assets/NoMeta.txt")));
+    }
+
+    #[test]
+    fn extension_checks_report_unreadable_blobs_and_keep_walking() {
+        let (dir, repo, tree_id) = repo_with_files(&[
+            ("Assets/Broken.wav", b"broken"),
+            ("Assets/Loud.wav", b"loud"),
+            ("Assets/Broken.unity", b"broken scene"),
+            ("Assets/Binary.unity", b"binary scene"),
+        ]);
+        let tree = repo.find_tree(tree_id).unwrap();
+        for path in ["Assets/Broken.wav", "Assets/Broken.unity"] {
+            let id = tree.get_path(Path::new(path)).unwrap().id().to_string();
+            std::fs::remove_file(
+                dir.path()
+                    .join(".git/objects")
+                    .join(&id[..2])
+                    .join(&id[2..]),
+            )
+            .unwrap();
+        }
+
+        let config = CheckConfig::new().with_checks([Check::Audio, Check::Scene]);
+        let mut findings = findings(&config, &repo, tree_id)
+            .into_iter()
+            .map(|f| (f.check, f.path.display().to_string(), f.message))
+            .collect::<Vec<_>>();
+        findings.sort();
+        let unreadable = findings
+            .iter()
+            .filter(|(_, _, message)| message.starts_with("can't read object"))
+            .map(|(check, path, _)| (*check, path.as_str()))
+            .collect::<Vec<_>>();
+        // reported once, even though the scene check reads scenes twice
+        assert_eq!(
+            unreadable,
+            [
+                (Check::Audio, "Assets/Broken.wav"),
+                (Check::Scene, "Assets/Broken.unity")
+            ]
+        );
+        assert!(findings
+            .iter()
+            .any(|(check, path, _)| *check == Check::Audio && path == "Assets/Loud.wav"));
+        assert!(findings
+            .iter()
+            .any(|(check, path, _)| *check == Check::Scene && path == "Assets/Binary.unity"));
+    }
+
+    /// The status iter_tree_meta records for every path of the tree.
+    fn meta_names(repo: &Repository, tree_id: Oid) -> HashMap<PathBuf, MetaStatus> {
+        let tree = repo.find_tree(tree_id).unwrap();
+        let mut names = HashMap::new();
+        iter_tree_meta(
+            repo,
+            Path::new(""),
+            &tree,
+            &mut names,
+            &mut Vec::new(),
+            None,
+            &AssetRoots::default(),
+            &test_meta_ignore,
+            None,
+            &mut |finding| panic!("unexpected finding {:?}", finding),
+        )
+        .unwrap();
+        names
+    }
+
+    #[test]
+    fn iter_tree_meta_pairs_nested_assets() {
+        let meta: &[u8] = b"fileFormatVersion: 2\n";
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            ("Assets/a.meta", meta),
+            ("Assets/a/b.meta", meta),
+            ("Assets/a/b/c.meta", meta),
+            ("Assets/a/b/c/Foo.png", b"png"),
+            ("Assets/a/b/c/Foo.png.meta", meta),
+        ]);
+        let names = meta_names(&repo, tree_id);
+        for path in [
+            "Assets/a",
+            "Assets/a/b",
+            "Assets/a/b/c",
+            "Assets/a/b/c/Foo.png",
+        ] {
+            assert_eq!(
+                names[Path::new(path)],
+                MetaStatus::FILE | MetaStatus::META,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn iter_tree_meta_classifies_dotted_directories_by_kind() {
+        let meta: &[u8] = b"fileFormatVersion: 2\n";
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            ("Assets/My.Folder.meta", meta),
+            ("Assets/My.Folder/Foo.png", b"png"),
+            ("Assets/My.Folder/Foo.png.meta", meta),
+        ]);
+        let names = meta_names(&repo, tree_id);
+        assert_eq!(
+            names[Path::new("Assets/My.Folder")],
+            MetaStatus::FILE | MetaStatus::META
+        );
+        // the directory is neither a `My` asset nor the meta of one
+        assert!(!names.contains_key(Path::new("Assets/My")));
+        assert_eq!(
+            names[Path::new("Assets/My.Folder/Foo.png")],
+            MetaStatus::FILE | MetaStatus::META
+        );
+    }
+
+    #[test]
+    fn meta_with_the_tree_root_as_asset_root() {
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            ("Foo.png", b"png"),
+            ("Foo.png.meta", b"fileFormatVersion: 2\n"),
+            ("Bar.txt", b"text\n"),
+        ]);
+        let config = CheckConfig::new()
+            .with_checks([Check::Meta])
+            .with_assets_root(".");
+        let findings = findings(&config, &repo, tree_id);
+        let paths = findings
+            .iter()
+            .map(|f| f.path.as_path())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [Path::new("Bar.txt")]);
+        assert_eq!(findings[0].message, "missing meta file");
+    }
+
+    #[test]
+    fn gitlinks_under_assets_are_skipped() {
+        let meta: &[u8] = b"fileFormatVersion: 2\n";
+        let (_dir, repo, _) = repo_with_files(&[
+            (".gitattributes", b"Plugin merge=lfs filter=lfs\n"),
+            ("Assets/plugin.meta", meta),
+            ("Assets/plugin/Foo.cs", b"class Foo {}\n"),
+            ("Assets/plugin/Foo.cs.meta", meta),
+        ]);
+        // a submodule without a meta, whose name collides with a directory and whose commit
+        // isn't in the repository
+        let mut index = repo.index().unwrap();
+        index
+            .add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o160000,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap(),
+                flags: 0,
+                flags_extended: 0,
+                path: b"Assets/Plugin".to_vec(),
+            })
+            .unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        assert!(is_submodule(
+            &tree.get_path(Path::new("Assets/Plugin")).unwrap()
+        ));
+
+        let config = CheckConfig::new().with_checks([Check::Meta, Check::Case, Check::Lfs]);
+        let findings = findings(&config, &repo, tree_id);
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn shared_walk_finds_what_each_check_walking_finds() {
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            ("Assets/Empty.txt", b""),
+            ("Assets/Empty.txt.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/A/Logo.png", b"png"),
+            ("Assets/A/Logo.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/A/Icon.PNG", b"png"),
+            ("Assets/A/Icon.PNG.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/B/Logo.png", b"png"),
+            ("Assets/B/Logo.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/Skipped/Empty.txt", b""),
+            ("Assets/Skipped/Empty.txt.meta", b"fileFormatVersion: 2\n"),
+        ]);
+        let config = CheckConfig {
+            exclude: vec!["Assets/Skipped".into()],
+            ..CheckConfig::new().with_checks([
+                Check::ExtensionCase,
+                Check::DuplicateBasenames,
+                Check::EmptyFiles,
+            ])
+        };
+        let run = |strategy| {
+            let mut findings = Vec::new();
+            check_tree_with(&config, &repo, tree_id, None, strategy, |finding| {
+                findings.push((finding.check, finding.path, finding.message))
+            })
+            .unwrap();
+            findings.sort();
+            findings
+        };
+
+        let findings = run(WalkStrategy::Shared);
+        assert_eq!(findings, run(WalkStrategy::PerCheck));
+        let paths = findings
+            .iter()
+            .map(|(check, path, _)| (*check, path.to_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                (Check::DuplicateBasenames, "Assets/A/Logo.png"),
+                (Check::DuplicateBasenames, "Assets/B/Logo.png"),
+                (Check::EmptyFiles, "Assets/Empty.txt"),
+                (Check::ExtensionCase, "Assets/A/Icon.PNG"),
+            ]
+        );
+    }
+
+    #[test]
+    fn builder_sets_the_lfs_threshold_and_normalizes_importers() {
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            (".gitattributes", b"*.png merge=lfs filter=lfs -text\n"),
+            ("Assets/Small.png", &[0; 200]),
+            ("Assets/Small.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/Large.png", &[0; 2000]),
+            ("Assets/Large.png.meta", b"fileFormatVersion: 2\n"),
+            ("Assets/Large.tmp", &[0; 2000]),
+        ]);
+        let config = CheckConfig::new()
+            .with_assets_root("Assets/")
+            .with_lfs_threshold(1000)
+            .with_ignore_pattern("*.tmp")
+            .with_importer(".PSD", "TextureImporter")
+            .with_check(Check::Lfs)
+            .build();
+        assert_eq!(config.importers["psd"], "TextureImporter");
+
+        let findings = findings(&config, &repo, tree_id);
+        let paths = findings
+            .iter()
+            .map(|finding| finding.path.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["Assets/Large.png"], "{:?}", findings);
+        assert_eq!(findings[0].message, "should be in LFS, size=2000");
+    }
+
+    #[test]
+    fn malformed_pointer_size_is_an_invalid_pointer() {
+        let pointer = b"version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize -1\n";
+        let (_dir, repo, tree_id) = repo_with_files(&[
+            (".gitattributes", b"*.png merge=lfs filter=lfs -text\n"),
+            ("Assets/Logo.png", pointer),
+            ("Assets/Logo.png.meta", b"fileFormatVersion: 2\n"),
+        ]);
+
+        let config = CheckConfig::new().with_checks([Check::Lfs]);
+        assert!(findings(&config, &repo, tree_id).is_empty());
+        let config = CheckConfig::new().with_checks([Check::LfsInvalidOid]);
+        let findings = findings(&config, &repo, tree_id);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert_eq!(findings[0].path, Path::new("Assets/Logo.png"));
+        assert_eq!(
+            findings[0].message,
+            "invalid lfs pointer size, expected a non-negative integer"
+        );
+    }
+
+    fn pairs(findings: &[(&str, &str)]) -> Vec<(String, String)> {
+        findings
+            .iter()
+            .map(|(path, message)| (path.to_string(), message.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn os_metadata_reports_os_files() {
+        let config = CheckConfig::new().with_checks([Check::OsMetadata]);
+        let findings = tree_findings(&config, &[("Assets/.DS_Store", b"x"), ("Thumbs.db", b"x")]);
+        assert_eq!(
+            findings,
+            pairs(&[
+                (
+                    "Assets/.DS_Store",
+                    "os metadata file, consider adding \".DS_Store\" to .gitignore"
+                ),
+                (
+                    "Thumbs.db",
+                    "os metadata file, consider adding \"Thumbs.db\" to .gitignore"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn os_metadata_accepts_regular_files() {
+        let config = CheckConfig::new().with_checks([Check::OsMetadata]);
+        let findings = tree_findings(
+            &config,
+            &[("Assets/DS_Store.txt", b"x"), ("Assets/Thumbs.png", b"x")],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn meta_reports_stale_empty_and_orphan_metas() {
+        let config = CheckConfig::new().with_checks([Check::Meta]);
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Gone.png.meta", META),
+                (
+                    "Assets/Deleted.meta",
+                    b"fileFormatVersion: 2\nfolderAsset: yes\n",
+                ),
+                (
+                    "Assets/Old.meta",
+                    b"fileFormatVersion: 2\nfolderAsset: yes\n",
+                ),
+                ("Assets/Old/Tree.png.meta", META),
+            ],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[
+                (
+                    "Assets/Deleted",
+                    "orphan folder meta, the folder was deleted or holds no tracked files"
+                ),
+                ("Assets/Gone.png", "meta file without asset"),
+                (
+                    "Assets/Old",
+                    "directory contains only meta files, its assets were deleted"
+                ),
+                ("Assets/Old/Tree.png", "meta file without asset"),
+            ])
+        );
+    }
+
+    #[test]
+    fn meta_reports_empty_directories() {
+        let (_dir, repo, tree_id) =
+            repo_with_files(&[("Assets/Tree.png", b"png"), ("Assets/Tree.png.meta", META)]);
+        // git can't stage an empty directory, but other tools can write its tree
+        let empty = repo.treebuilder(None).unwrap().write().unwrap();
+        let assets = repo
+            .find_tree(tree_id)
+            .unwrap()
+            .get_name("Assets")
+            .unwrap()
+            .id();
+        let mut builder = repo
+            .treebuilder(Some(&repo.find_tree(assets).unwrap()))
+            .unwrap();
+        builder.insert("Empty", empty, 0o040000).unwrap();
+        let assets = builder.write().unwrap();
+        let mut builder = repo
+            .treebuilder(Some(&repo.find_tree(tree_id).unwrap()))
+            .unwrap();
+        builder.insert("Assets", assets, 0o040000).unwrap();
+        let tree_id = builder.write().unwrap();
+
+        let config = CheckConfig::new().with_checks([Check::Meta]);
+        let findings = findings(&config, &repo, tree_id);
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert_eq!(findings[0].path, Path::new("Assets/Empty"));
+        assert_eq!(findings[0].message, "empty directory");
+    }
+
+    #[test]
+    fn meta_accepts_assets_with_their_metas() {
+        let config = CheckConfig::new().with_checks([Check::Meta]);
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Tree.png", b"png"),
+                ("Assets/Tree.png.meta", META),
+                (
+                    "Assets/Trees.meta",
+                    b"fileFormatVersion: 2\nfolderAsset: yes\n",
+                ),
+                ("Assets/Trees/Oak.png", b"png"),
+                ("Assets/Trees/Oak.png.meta", META),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn importer_reports_an_unexpected_importer() {
+        let config = CheckConfig::new().with_checks([Check::Importer]);
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Logo.png", b"png"),
+                (
+                    "Assets/Logo.png.meta",
+                    b"fileFormatVersion: 2\nAudioImporter:\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Logo.png.meta",
+                "unexpected importer AudioImporter, expected TextureImporter"
+            )])
+        );
+    }
+
+    #[test]
+    fn importer_accepts_the_expected_importer() {
+        let config = CheckConfig::new()
+            .with_checks([Check::Importer])
+            .with_importer("bytes", "TextScriptImporter");
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Logo.png", b"png"),
+                (
+                    "Assets/Logo.png.meta",
+                    b"fileFormatVersion: 2\nTextureImporter:\n",
+                ),
+                ("Assets/Data.bytes", b"data"),
+                (
+                    "Assets/Data.bytes.meta",
+                    b"fileFormatVersion: 2\nTextScriptImporter:\n",
+                ),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    /// `Assets/A` and `Assets/a` collide, and so do their children unless compared by directory.
+    const CASE_FILES: &[(&str, &[u8])] = &[
+        ("Assets/A/Logo.png", b"png"),
+        ("Assets/a/logo.png", b"png"),
+        ("Assets/B/Logo.png", b"png"),
+    ];
+
+    #[test]
+    fn case_reports_paths_colliding_anywhere_in_the_tree() {
+        let config = CheckConfig::new()
+            .with_checks([Check::Case])
+            .with_case_scope(CaseScope::Global);
+        let paths = tree_findings(&config, CASE_FILES)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["Assets/a", "Assets/a/logo.png"]);
+    }
+
+    #[test]
+    fn case_with_dir_scope_compares_siblings_only() {
+        let config = CheckConfig::new()
+            .with_checks([Check::Case])
+            .with_case_scope(CaseScope::Dir);
+        let findings = tree_findings(&config, CASE_FILES);
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/a",
+                "case-insensitive duplicated entry, rename it or merge it into Assets/A"
+            )])
+        );
+    }
+
+    #[test]
+    fn manifest_reports_invalid_versions() {
+        let config = CheckConfig::new().with_checks([Check::Manifest]);
+        let findings = tree_findings(
+            &config,
+            &[(
+                "Packages/manifest.json",
+                br#"{"dependencies": {"com.unity.ugui": "1.0.0", "com.example.tool": "latest"}}"#,
+            )],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Packages/manifest.json",
+                "invalid version for com.example.tool: \"latest\""
+            )])
+        );
+    }
+
+    #[test]
+    fn manifest_accepts_semver_git_and_file_versions() {
+        let config = CheckConfig::new().with_checks([Check::Manifest]);
+        let findings = tree_findings(
+            &config,
+            &[(
+                "Packages/manifest.json",
+                br#"{"dependencies": {"a": "1.2.3-preview.1", "b": "https://example.com/b.git", "c": "file:../c"}}"#,
+            )],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn subtree_reports_findings_inside_it_with_repository_paths() {
+        let config = CheckConfig::new()
+            .with_checks([Check::Meta])
+            .with_subtree("Assets/Characters");
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Characters/Hero.png", b"png"),
+                ("Assets/Characters/Hero.png.meta", META),
+                ("Assets/Characters/Villain.png", b"png"),
+            ],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[("Assets/Characters/Villain.png", "missing meta file")])
+        );
+    }
+
+    #[test]
+    fn subtree_skips_findings_outside_of_it() {
+        let config = CheckConfig::new()
+            .with_checks([Check::Meta, Check::Case])
+            .with_subtree("Assets/Characters");
+        let findings = tree_findings(
+            &config,
+            &[
+                ("Assets/Characters/Hero.png", b"png"),
+                ("Assets/Characters/Hero.png.meta", META),
+                ("Assets/Villain.png", b"png"),
+                ("Assets/villain.png", b"png"),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn asmdef_references_reports_missing_guids() {
+        let config = CheckConfig::new().with_checks([Check::AsmdefReferences]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (
+                    "Assets/Game.asmdef",
+                    br#"{"name": "Game", "references": ["GUID:0123456789abcdef0123456789abcdef", "Unity.TextMeshPro"]}"#,
+                ),
+                ("Assets/Broken.asmdef", b"{"),
+            ],
+        );
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert!(findings[0].1.starts_with("invalid json"), "{:?}", findings);
+        assert_eq!(
+            findings[1],
+            (
+                "Assets/Game.asmdef".to_owned(),
+                "references missing assembly definition GUID:0123456789abcdef0123456789abcdef"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn asmdef_references_accepts_guids_of_the_tree() {
+        let config = CheckConfig::new().with_checks([Check::AsmdefReferences]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (
+                    "Assets/Game.asmdef",
+                    br#"{"name": "Game", "references": ["GUID:0123456789ABCDEF0123456789ABCDEF"]}"#,
+                ),
+                ("Assets/Core/Core.asmdef", br#"{"name": "Core"}"#),
+                (
+                    "Assets/Core/Core.asmdef.meta",
+                    &meta("0123456789abcdef0123456789abcdef"),
+                ),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn gitignore_reports_committed_ignored_files() {
+        let config = CheckConfig::new().with_checks([Check::Gitignore]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (".gitignore", b"*.log\n/Library/\n"),
+                ("Assets/debug.log", b"log"),
+                ("Library/cache", b"cache"),
+            ],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[
+                (
+                    "Assets/debug.log",
+                    "committed but matches \"*.log\" in .gitignore, untrack it with `git rm --cached`"
+                ),
+                (
+                    "Library",
+                    "committed but matches \"/Library/\" in .gitignore, untrack it with `git rm -r --cached`"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn gitignore_accepts_files_not_ignored() {
+        let config = CheckConfig::new().with_checks([Check::Gitignore]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (".gitignore", b"*.log\n!keep.log\n"),
+                ("Assets/keep.log", b"log"),
+                ("Assets/debug.txt", b"log"),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn lfs_invalid_oid_reports_malformed_oids() {
+        let pointer = b"version https://git-lfs.github.com/spec/v1\noid sha256:4D7A\nsize 12\n";
+        let config = CheckConfig::new().with_checks([Check::LfsInvalidOid]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (".gitattributes", b"*.png merge=lfs filter=lfs -text\n"),
+                ("Assets/Logo.png", pointer),
+            ],
+        );
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Logo.png",
+                "invalid lfs pointer oid \"4D7A\", expected 64 lowercase hex characters"
+            )])
+        );
+    }
+
+    #[test]
+    fn lfs_invalid_oid_accepts_valid_pointers() {
+        let config = CheckConfig::new().with_checks([Check::LfsInvalidOid]);
+        let findings = tree_findings(
+            &config,
+            &[
+                (".gitattributes", b"*.png merge=lfs filter=lfs -text\n"),
+                ("Assets/Logo.png", POINTER),
+            ],
+        );
+        assert_eq!(findings, []);
+    }
+
+    fn collect(run: impl FnOnce(&mut Emit) -> Result<(), CheckError>) -> Vec<(String, String)> {
+        let mut findings = Vec::new();
+        run(&mut |finding| findings.push((finding.path.display().to_string(), finding.message)))
+            .unwrap();
+        findings.sort();
+        findings
+    }
+
+    /// `main` adds `Assets/A.png` with the guid `aa`, `other`, branched before, adds
+    /// `Assets/B.png` with `b_guid` and renames `Assets/Shared.png` of their merge base.
+    fn repo_with_branches(b_guid: &str) -> (tempfile::TempDir, Repository) {
+        let (dir, repo, _) = repo_with_files(&[
+            ("Assets/Shared.png", b"png"),
+            ("Assets/Shared.png.meta", &meta("5a")),
+        ]);
+        {
+            let base = commit_all(&repo, "Base");
+            write_files(
+                &repo,
+                &[("Assets/A.png", b"png"), ("Assets/A.png.meta", &meta("aa"))],
+            );
+            let main = commit_all(&repo, "Add A");
+            repo.branch("main", &main, true).unwrap();
+
+            repo.branch("other", &base, true).unwrap();
+            repo.set_head("refs/heads/other").unwrap();
+            repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+                .unwrap();
+            write_files(
+                &repo,
+                &[
+                    ("Assets/B.png", b"png"),
+                    ("Assets/B.png.meta", &meta(b_guid)),
+                ],
+            );
+            let assets = repo.workdir().unwrap().join("Assets");
+            for (from, to) in [
+                ("Shared.png", "Moved.png"),
+                ("Shared.png.meta", "Moved.png.meta"),
+            ] {
+                std::fs::rename(assets.join(from), assets.join(to)).unwrap();
+            }
+            commit_all(&repo, "Add B");
+        }
+        (dir, repo)
+    }
+
+    #[test]
+    fn guid_reports_a_guid_reused_on_another_branch() {
+        let (_dir, repo) = repo_with_branches("aa");
+        let config = CheckConfig::new();
+        let mut tips = branch_tips(&repo).unwrap();
+        tips.sort_by(|a, b| a.0.cmp(&b.0));
+        let findings =
+            collect(|emit| test_duplicate_guids_across_commits(&config, &repo, &tips, emit));
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/B.png",
+                "guid aa on branch other is also used by Assets/A.png on branch main"
+            )])
+        );
+    }
+
+    #[test]
+    fn guid_accepts_guids_from_the_merge_base() {
+        let (_dir, repo) = repo_with_branches("bb");
+        let config = CheckConfig::new();
+        let tips = branch_tips(&repo).unwrap();
+        let findings =
+            collect(|emit| test_duplicate_guids_across_commits(&config, &repo, &tips, emit));
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn staged_meta_reports_an_asset_staged_without_its_meta() {
+        let (_dir, repo, _) = repo_with_files(&[]);
+        write_files(
+            &repo,
+            &[("Assets/Tree.png", b"png"), ("Assets/Tree.png.meta", META)],
+        );
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("Assets/Tree.png")).unwrap();
+        let config = CheckConfig::new();
+        let findings = collect(|emit| test_staged_metas(&config, &repo, &index, None, emit));
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Tree.png",
+                "staged without its meta, Assets/Tree.png.meta isn't staged"
+            )])
+        );
+    }
+
+    #[test]
+    fn staged_meta_accepts_an_asset_staged_with_its_meta() {
+        let (_dir, repo, _) =
+            repo_with_files(&[("Assets/Tree.png", b"png"), ("Assets/Tree.png.meta", META)]);
+        // a meta missing from the working directory too is left to the meta check
+        write_files(&repo, &[("Assets/Rock.png", b"png")]);
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("Assets/Rock.png")).unwrap();
+        let config = CheckConfig::new();
+        let findings = collect(|emit| test_staged_metas(&config, &repo, &index, None, emit));
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn meta_commit_reports_a_meta_committed_after_its_asset() {
+        let (_dir, repo, _) = repo_with_files(&[("README.md", b"readme")]);
+        let base = commit_all(&repo, "Init");
+        write_files(&repo, &[("Assets/Tree.png", b"png")]);
+        let asset = commit_all(&repo, "Add the tree");
+        write_files(&repo, &[("Assets/Tree.png.meta", META)]);
+        let head = commit_all(&repo, "Add its meta");
+
+        let config = CheckConfig::new();
+        let findings = collect(|emit| test_meta_commits(&config, &repo, &base, &head, emit));
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Tree.png",
+                &format!(
+                    "first committed in {} but its meta in {}",
+                    asset.id(),
+                    head.id()
+                )
+            )])
+        );
+    }
+
+    #[test]
+    fn meta_commit_accepts_an_asset_committed_with_its_meta() {
+        let (_dir, repo, _) = repo_with_files(&[("README.md", b"readme")]);
+        let base = commit_all(&repo, "Init");
+        write_files(
+            &repo,
+            &[("Assets/Tree.png", b"png"), ("Assets/Tree.png.meta", META)],
+        );
+        commit_all(&repo, "Add the tree");
+        write_files(&repo, &[("Assets/Tree.png", b"png, edited")]);
+        let head = commit_all(&repo, "Edit the tree");
+
+        let config = CheckConfig::new();
+        let findings = collect(|emit| test_meta_commits(&config, &repo, &base, &head, emit));
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn guid_stability_reports_a_regenerated_meta() {
+        let (_dir, repo, _) = repo_with_files(&[
+            ("Assets/Tree.png", b"png"),
+            ("Assets/Tree.png.meta", &meta("aa")),
+        ]);
+        let old = commit_all(&repo, "Add the tree");
+        write_files(&repo, &[("Assets/Tree.png.meta", &meta("bb"))]);
+        let new = commit_all(&repo, "Reimport the tree");
+
+        let config = CheckConfig::new();
+        let (old, new) = (old.tree().unwrap(), new.tree().unwrap());
+        let findings = collect(|emit| test_guid_stability(&config, &repo, &old, &new, emit));
+        assert_eq!(
+            findings,
+            pairs(&[("Assets/Tree.png.meta", "guid changed from aa to bb")])
+        );
+    }
+
+    #[test]
+    fn guid_stability_accepts_a_renamed_meta_keeping_its_guid() {
+        let guid = meta("0123456789abcdef0123456789abcdef");
+        let (_dir, repo, _) =
+            repo_with_files(&[("Assets/Tree.png", b"png"), ("Assets/Tree.png.meta", &guid)]);
+        let old = commit_all(&repo, "Add the tree");
+        let workdir = repo.workdir().unwrap();
+        std::fs::create_dir(workdir.join("Assets/Trees")).unwrap();
+        for name in ["Tree.png", "Tree.png.meta"] {
+            std::fs::rename(
+                workdir.join("Assets").join(name),
+                workdir.join("Assets/Trees").join(name),
+            )
+            .unwrap();
+        }
+        write_files(&repo, &[("Assets/Trees.meta", META)]);
+        let new = commit_all(&repo, "Move the tree");
+
+        let config = CheckConfig::new();
+        let (old, new) = (old.tree().unwrap(), new.tree().unwrap());
+        let findings = collect(|emit| test_guid_stability(&config, &repo, &old, &new, emit));
+        assert_eq!(findings, []);
+    }
+
+    #[test]
+    fn history_blobs_reports_large_blobs_deleted_since() {
+        let (_dir, repo, _) = repo_with_files(&[("Assets/Big.psd", &[1; 300])]);
+        let added = commit_all(&repo, "Add the source");
+        let id = added
+            .tree()
+            .unwrap()
+            .get_path(Path::new("Assets/Big.psd"))
+            .unwrap()
+            .id();
+        std::fs::remove_file(repo.workdir().unwrap().join("Assets/Big.psd")).unwrap();
+        write_files(&repo, &[("README.md", b"readme")]);
+        commit_all(&repo, "Remove the source");
+
+        let config = CheckConfig::new().with_history_blob_threshold(100);
+        let findings = collect(|emit| test_large_history_objects(&config, &repo, emit));
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Big.psd",
+                &format!(
+                    "blob {} of 300 bytes added in commit {} is larger than 100 bytes and stays in the history even once deleted",
+                    id,
+                    added.id()
+                )
+            )])
+        );
+    }
+
+    #[test]
+    fn history_blobs_accepts_small_blobs() {
+        let (_dir, repo, _) = repo_with_files(&[("Assets/Small.psd", &[1; 100])]);
+        commit_all(&repo, "Add the source");
+
+        let config = CheckConfig::new().with_history_blob_threshold(100);
+        let findings = collect(|emit| test_large_history_objects(&config, &repo, emit));
+        assert_eq!(findings, []);
+    }
+
+    #[cfg(feature = "locks")]
+    fn lock_findings(locks: &str) -> Vec<(String, String)> {
+        let (url, server) = crate::test_http::serve(vec![(200, locks.to_owned())]);
+        let (_dir, repo, tree_id) =
+            repo_with_files(&[("Assets/Hero.png", b"png"), ("Assets/Villain.png", b"png")]);
+        let mut config = repo.config().unwrap();
+        config.set_str("lfs.url", &url).unwrap();
+        config.set_str("user.name", "bob").unwrap();
+
+        let config = CheckConfig::new().with_checks([Check::LfsLocks]);
+        let mut findings = findings(&config, &repo, tree_id)
+            .into_iter()
+            .map(|finding| (finding.path.display().to_string(), finding.message))
+            .collect::<Vec<_>>();
+        findings.sort();
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /locks "), "{:?}", requests);
+        findings
+    }
+
+    #[cfg(feature = "locks")]
+    #[test]
+    fn lfs_locks_reports_files_locked_by_others() {
+        let findings = lock_findings(
+            r#"{"locks": [{"id": "1", "path": "Assets/Hero.png", "locked_at": "2024-01-01T00:00:00Z", "owner": {"name": "alice"}}]}"#,
+        );
+        assert_eq!(
+            findings,
+            pairs(&[(
+                "Assets/Hero.png",
+                "locked by alice since 2024-01-01T00:00:00Z, lock id 1"
+            )])
+        );
+    }
+
+    #[cfg(feature = "locks")]
+    #[test]
+    fn lfs_locks_accepts_own_locks_and_files_not_in_the_tree() {
+        let findings = lock_findings(
+            r#"{"locks": [
+                {"id": "1", "path": "Assets/Hero.png", "owner": {"name": "bob"}},
+                {"id": "2", "path": "Assets/Deleted.png", "owner": {"name": "alice"}}
+            ]}"#,
+        );
+        assert_eq!(findings, []);
+    }
+
+    #[cfg(not(feature = "locks"))]
+    #[test]
+    fn lfs_locks_needs_the_locks_feature() {
+        let (_dir, repo, tree_id) = repo_with_files(&[("Assets/Hero.png", b"png")]);
+        repo.config()
+            .unwrap()
+            .set_str("lfs.url", "http://127.0.0.1:1")
+            .unwrap();
+        let config = CheckConfig::new().with_checks([Check::LfsLocks]);
+        let error = check_all_with(&config, &repo, tree_id, None, |_| {}).unwrap_err();
+        assert!(error.to_string().contains("`locks` feature"), "{}", error);
+    }
+}
@@ -0,0 +1,15 @@
+//! Checks of Unity projects stored with git-lfs, run by the `checklfs` binary. Each check walks
+//! the tree on its own, [`check_tree_with`] can instead replay a single shared walk to the checks
+//! that walk the whole tree, and `benches/walk.rs` times both.
+
+pub mod attributes;
+pub mod checks;
+pub mod locks;
+pub mod otel;
+#[cfg(all(test, any(feature = "locks", feature = "otel")))]
+mod test_http;
+
+pub use checks::{
+    check_all_with, check_tree_with, Check, CheckConfig, CheckError, Finding, ReportFormat,
+    Severity, WalkStrategy,
+};
@@ -0,0 +1,117 @@
+//! Client for the git-lfs file locking API, see
+//! https://github.com/git-lfs/git-lfs/blob/main/docs/api/locking.md.
+
+use anyhow::Result;
+use git2::Repository;
+
+/// An active lock on a file as reported by the lfs server.
+#[derive(Debug, serde::Deserialize)]
+pub struct Lock {
+    pub id: String,
+    /// Repository-relative path of the locked file.
+    pub path: String,
+    #[serde(default)]
+    pub locked_at: String,
+    #[serde(default)]
+    pub owner: Option<LockOwner>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LockOwner {
+    pub name: String,
+}
+
+#[cfg(feature = "locks")]
+#[derive(Debug, serde::Deserialize)]
+struct LockList {
+    locks: Vec<Lock>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// Url of the lfs server, `lfs.url` if configured, else derived from the `origin` remote the
+/// way git-lfs does: `<remote>.git/info/lfs`, with ssh remotes mapped to https.
+pub fn lfs_url(repo: &Repository) -> Result<String> {
+    let config = repo.config()?;
+    if let Ok(url) = config.get_string("lfs.url") {
+        return Ok(url.trim_end_matches('/').to_owned());
+    }
+    let remote = match config.get_string("remote.origin.url") {
+        Ok(remote) => remote,
+        Err(_) => anyhow::bail!("no lfs.url or remote.origin.url configured"),
+    };
+
+    let url = if let Some(rest) = remote.strip_prefix("ssh://") {
+        // ssh://git@host[:port]/path, the ssh port has no meaning over https
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, rest)| rest);
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = host.split(':').next().unwrap_or(host);
+        format!("https://{}/{}", host, path)
+    } else if remote.starts_with("https://") || remote.starts_with("http://") {
+        remote
+    } else if let Some((host, path)) = remote.split_once(':') {
+        // scp-like git@host:path
+        let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+        format!("https://{}/{}", host, path.trim_start_matches('/'))
+    } else {
+        anyhow::bail!("can't derive the lfs url from remote {:?}", remote);
+    };
+
+    let url = url.trim_end_matches('/');
+    if url.ends_with(".git") {
+        Ok(format!("{}/info/lfs", url))
+    } else {
+        Ok(format!("{}.git/info/lfs", url))
+    }
+}
+
+/// Fetches every active lock, following the server's pagination. Credentials are read from
+/// CHECKLFS_GIT_USERNAME/CHECKLFS_GIT_PASSWORD like for `--repo-url`.
+#[cfg(feature = "locks")]
+pub fn fetch_locks(url: &str) -> Result<Vec<Lock>> {
+    let client = reqwest::blocking::Client::new();
+    let username = std::env::var("CHECKLFS_GIT_USERNAME").ok();
+    let password = std::env::var("CHECKLFS_GIT_PASSWORD").ok();
+
+    let mut locks = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!("{}/locks", url))
+            .header("Accept", "application/vnd.git-lfs+json");
+        if let Some(cursor) = &cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+        if let Some(username) = &username {
+            request = request.basic_auth(username, password.as_ref());
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("lfs lock request to {} failed: {}", url, status);
+        }
+        let list: LockList = serde_json::from_str(&response.text()?)?;
+        locks.extend(list.locks);
+
+        match list.next_cursor {
+            Some(next) if !next.is_empty() => cursor = Some(next),
+            _ => break,
+        }
+    }
+    Ok(locks)
+}
+
+#[cfg(not(feature = "locks"))]
+pub fn fetch_locks(_url: &str) -> Result<Vec<Lock>> {
+    anyhow::bail!("--check-lfs-locks needs checklfs built with the `locks` feature")
+}
+
+/// Name the locks of the invoking user are held by: CHECKLFS_GIT_USERNAME if set, else
+/// `user.name`.
+pub fn current_user(repo: &Repository) -> Option<String> {
+    std::env::var("CHECKLFS_GIT_USERNAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(|| repo.config().ok()?.get_string("user.name").ok())
+}
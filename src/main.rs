@@ -1,291 +1,2060 @@
 use anyhow::Result;
 use argh::*;
+use checklfs::checks::*;
+use checklfs::otel;
 use git2::*;
 use log::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-#[derive(Default)]
-struct MetaStatus {
-    file: bool,
-    meta: bool,
+/// Identity of a checked commit, kept for the summary.
+#[derive(Debug, Clone)]
+struct CommitInfo {
+    id: Oid,
+    summary: String,
+    author: String,
 }
 
-impl MetaStatus {
-    fn file() -> Self {
-        MetaStatus {
-            file: true,
-            meta: false,
+impl CommitInfo {
+    fn new(commit: &Commit) -> Self {
+        let author = commit.author();
+        CommitInfo {
+            id: commit.id(),
+            summary: commit.summary().unwrap_or("").to_owned(),
+            author: format!(
+                "{} <{}>",
+                author.name().unwrap_or(""),
+                author.email().unwrap_or("")
+            ),
         }
     }
-    fn meta() -> Self {
-        MetaStatus {
-            file: false,
-            meta: true,
+}
+
+/// Findings collected from one or more checked trees.
+#[derive(Default, Debug)]
+struct Report {
+    findings: Vec<Finding>,
+    commits: Vec<CommitInfo>,
+    /// Time spent in each check, summed over every checked tree.
+    timings: BTreeMap<Check, Duration>,
+    /// Every run of a check against a tree, for `--otel-endpoint`.
+    spans: Vec<otel::CheckSpan>,
+}
+
+impl Report {
+    fn error_count(&self, check: Check) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.check == check && f.severity == Severity::Error)
+            .count()
+    }
+
+    fn total(&self) -> usize {
+        Check::ALL
+            .iter()
+            .map(|&check| self.error_count(check))
+            .sum()
+    }
+
+    fn add(&mut self, other: Report) {
+        self.findings.extend(other.findings);
+        self.commits.extend(other.commits);
+        for (check, elapsed) in other.timings {
+            *self.timings.entry(check).or_default() += elapsed;
+        }
+        self.spans.extend(other.spans);
+    }
+
+    /// The report of the checks run against `tree_id` by [`check_all_with`].
+    fn from_tree(tree_id: Oid, findings: Vec<Finding>, timings: Vec<CheckTiming>) -> Report {
+        let mut report = Report {
+            findings,
+            ..Default::default()
+        };
+        for timing in timings {
+            report.timings.insert(timing.check, timing.elapsed);
+            report.spans.push(otel::CheckSpan {
+                name: timing.check.name().to_owned(),
+                tree_id: tree_id.to_string(),
+                error_count: report.error_count(timing.check),
+                start: timing.start,
+                duration: timing.elapsed,
+            });
         }
+        report
+    }
+
+    /// Bits of the failed checks, plus [`WARNINGS_EXIT_CODE`] for any warning with
+    /// `fail_on_warnings`.
+    fn exit_code(&self, fail_on_warnings: bool) -> i32 {
+        let exit_code = Check::ALL
+            .iter()
+            .filter(|&&check| self.error_count(check) > 0)
+            .fold(0, |exit_code, check| exit_code | check.exit_code());
+        let warned = self
+            .findings
+            .iter()
+            .any(|f| f.severity == Severity::Warning);
+        if fail_on_warnings && warned {
+            exit_code | WARNINGS_EXIT_CODE
+        } else {
+            exit_code
+        }
+    }
+
+    /// Error counts of the enabled `checks`, e.g. `meta-errors=1, lfs-errors=0`. A check outside
+    /// of `checks` is only listed when it reported errors anyway.
+    fn summary(&self, checks: &HashSet<Check>) -> String {
+        Check::ALL
+            .iter()
+            .map(|&check| (check, self.error_count(check)))
+            .filter(|&(check, count)| checks.contains(&check) || count > 0)
+            .map(|(check, count)| format!("{}-errors={}", check.name(), count))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
-fn test_meta<P: AsRef<Path>>(repo_root: P, commit_id: &str) -> Result<usize> {
-    info!("checking meta files");
+/// Exit code bit of `--fail-on-warnings`, distinct from the bits of the checks so that warnings
+/// can be told apart from errors.
+const WARNINGS_EXIT_CODE: i32 = 128;
 
-    let repo = git2::Repository::open(repo_root)?;
-    let commit_id = git2::Oid::from_str(commit_id)?;
-    let tree = repo.find_tree(commit_id)?;
-    let root = PathBuf::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Csv,
+    /// One JSON object per line, written to stdout as findings are found.
+    Jsonl,
+    /// GitLab code quality report, a JSON array annotating merge requests.
+    Gitlab,
+}
 
-    let mut count = 0;
-    let mut name_set = HashMap::new();
-    iter_tree_meta(&repo, &root.as_path(), &tree, &mut name_set)?;
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
 
-    for (path, status) in name_set {
-        if !path.starts_with("Assets/") || (path == Path::new("Assets")) {
-            continue;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "csv" => Ok(OutputFormat::Csv),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "gitlab" => Ok(OutputFormat::Gitlab),
+            _ => Err(format!("unknown output format: {}", s)),
         }
+    }
+}
 
-        if !(status.file && status.meta) {
-            count += 1;
-            error!(
-                "invalid status: path={:?}, file={}, meta={}",
-                path, status.file, status.meta
-            );
+/// When the log is colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorChoice {
+    /// Colored when stderr is a terminal, unless RUST_LOG_STYLE says otherwise.
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice: {}", s)),
         }
     }
-    Ok(count)
 }
 
-fn iter_tree_meta(
-    repo: &Repository,
-    prefix: &Path,
-    tree: &Tree,
-    names: &mut HashMap<PathBuf, MetaStatus>,
-) -> Result<()> {
-    for entry in tree.iter() {
-        let name = match entry.name() {
-            None => continue,
-            Some(name) => name,
-        };
+/// How the paths of findings are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathStyle {
+    /// Relative to the repository root, prefixed by the repository when several are checked.
+    Repo,
+    /// Relative to the working directory, e.g. for editors resolving paths of the output.
+    Cwd,
+    Absolute,
+}
 
-        // Unity3d ignores following files
-        if name.starts_with(".") || name.ends_with("~") {
-            continue;
+impl std::str::FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "repo" => Ok(PathStyle::Repo),
+            "cwd" => Ok(PathStyle::Cwd),
+            "absolute" => Ok(PathStyle::Absolute),
+            _ => Err(format!("unknown path style: {}", s)),
         }
+    }
+}
 
-        let obj = entry.to_object(repo)?;
-        let name = prefix.join(&name);
+/// `path` relative to `base`, both absolute, going up with `..` where they diverge.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+    relative
+}
 
-        match obj.kind() {
-            Some(ObjectType::Tree) => {
-                let tree = obj.peel_to_tree()?;
+/// Directory prepended to the paths of the findings of the repository at `path`.
+fn path_prefix(
+    style: PathStyle,
+    name: &str,
+    path: &Path,
+    several: bool,
+) -> Result<Option<PathBuf>> {
+    if style == PathStyle::Repo {
+        // keep findings from different repositories apart in the output
+        return Ok(Some(PathBuf::from(name)).filter(|_| several));
+    }
+    let repo = git2::Repository::open(path)?;
+    let root = std::fs::canonicalize(repo.workdir().unwrap_or(repo.path()))?;
+    match style {
+        PathStyle::Cwd => {
+            let cwd = std::fs::canonicalize(std::env::current_dir()?)?;
+            Ok(Some(relative_path(&root, &cwd)))
+        }
+        _ => Ok(Some(root)),
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// A finding as a single-line JSON object, with the suggested fix for
+/// [`ReportFormat::Long`].
+fn finding_json(f: &Finding, report: ReportFormat) -> String {
+    let mut json = serde_json::json!({
+        "check": f.check.name(),
+        "severity": f.severity.name(),
+        "path": f.path.to_string_lossy(),
+        "message": f.message,
+    });
+    if report == ReportFormat::Long {
+        json["suggestion"] = f.check.suggestion().into();
+    }
+    json.to_string()
+}
 
-                iter_tree_meta(repo, &name, &tree, names)?;
+/// A finding in the GitLab code quality schema. The fingerprint hashes the check and the path
+/// only, so a finding keeps its identity across runs even when its message changes.
+fn finding_gitlab(f: &Finding) -> serde_json::Value {
+    let path = f.path.to_string_lossy();
+    let key = format!("{}:{}", f.check.name(), path);
+    let fingerprint = Oid::hash_object(ObjectType::Blob, key.as_bytes())
+        .map(|oid| oid.to_string())
+        .unwrap_or(key);
+    serde_json::json!({
+        "description": f.message,
+        "check_name": f.check.name(),
+        "fingerprint": fingerprint,
+        "severity": match f.severity {
+            Severity::Error => "major",
+            Severity::Warning => "minor",
+        },
+        "location": {
+            "path": path,
+            "lines": { "begin": 1 },
+        },
+    })
+}
 
-                if let Some(v) = names.get_mut(&name) {
-                    v.file = true;
-                } else {
-                    names.insert(name, MetaStatus::file());
+fn write_findings<W: std::io::Write>(
+    mut w: W,
+    format: OutputFormat,
+    report: ReportFormat,
+    findings: &[Finding],
+) -> std::io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for f in findings {
+                match report {
+                    ReportFormat::PathOnly => writeln!(w, "{}", f.report(report))?,
+                    _ => writeln!(w, "{}: {}", f.severity.name(), f.report(report))?,
                 }
             }
-            Some(ObjectType::Blob) => {
-                let (base_path, is_meta) = if let Some(ext) = name.extension() {
-                    if ext == "meta" {
-                        let mut name = name.to_owned();
-                        name.set_extension("");
-                        (name, true)
-                    } else {
-                        (name.to_owned(), false)
-                    }
-                } else {
-                    (name.to_owned(), false)
-                };
-
-                if let Some(v) = names.get_mut(&base_path) {
-                    if is_meta {
-                        v.meta = true;
-                    } else {
-                        v.file = true;
-                    }
-                } else {
-                    if is_meta {
-                        names.insert(base_path, MetaStatus::meta());
-                    } else {
-                        names.insert(base_path, MetaStatus::file());
-                    }
-                }
+        }
+        OutputFormat::Csv if report == ReportFormat::Long => {
+            writeln!(w, "check,path,severity,message,suggestion")?;
+            for f in findings {
+                writeln!(
+                    w,
+                    "{},{},{},{},{}",
+                    f.check.name(),
+                    csv_field(&f.path.to_string_lossy()),
+                    f.severity.name(),
+                    csv_field(&f.message),
+                    csv_field(f.check.suggestion())
+                )?;
             }
-            _ => {
-                continue;
+        }
+        OutputFormat::Csv => {
+            writeln!(w, "check,path,severity,message")?;
+            for f in findings {
+                writeln!(
+                    w,
+                    "{},{},{},{}",
+                    f.check.name(),
+                    csv_field(&f.path.to_string_lossy()),
+                    f.severity.name(),
+                    csv_field(&f.message)
+                )?;
             }
         }
+        OutputFormat::Jsonl => {
+            for f in findings {
+                writeln!(w, "{}", finding_json(f, report))?;
+            }
+        }
+        OutputFormat::Gitlab => {
+            let issues = findings.iter().map(finding_gitlab).collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut w, &issues)?;
+            writeln!(w)?;
+        }
+    }
+    w.flush()
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(
+    description = "checklfs",
+    note = "Exit codes are OR-combined across checks and repositories, e.g. 10 means both meta and lfs checks failed.",
+    note = "Checks run by default: meta, lfs, lfs-invalid-oid, case, os-metadata, manifest, staged-meta. Opt-in: importer, guid, duplicate-basenames, fbx, texture, audio, video, assets-structure, scene, shader, meta-line-endings, meta-version, lfs-text, lfs-locks, binary, gitignore, empty-files, lfs-attributes, meta-case, commit-message, resources, streaming-assets, editor-folder-meta, submodules, unity-binary, extension-case, asmdef-references, history-blobs, guid-stability, meta-commit, component-length.",
+    note = "Every flag falls back to the environment variable CHECKLFS_<FLAG>, e.g. CHECKLFS_OUTPUT_FORMAT=csv or CHECKLFS_NO_FAIL=1; flags given on the command line take precedence over the environment, which takes precedence over the defaults. CHECKLFS_PATH is a `:`-separated list of paths, repeatable options are comma-separated and CHECKLFS_VERBOSE is the number of -v, e.g. CHECKLFS_VERBOSE=2 for -vv.",
+    note = "Project settings are read from .checklfs.toml in the checked commit, e.g. `[structure] required = [\"Assets/Scripts\"]` for the assets-structure check.",
+    error_code(1, "internal error"),
+    error_code(
+        2,
+        "meta checks failed: meta, importer, guid, meta-line-endings, meta-version, meta-case, editor-folder-meta, guid-stability, meta-commit, staged-meta"
+    ),
+    error_code(4, "naming checks failed: case, duplicate-basenames, extension-case, component-length"),
+    error_code(
+        8,
+        "lfs checks failed: lfs, fbx, texture, audio, video, scene, shader, lfs-invalid-oid, lfs-text, lfs-locks, binary, lfs-attributes, streaming-assets, unity-binary"
+    ),
+    error_code(16, "repository hygiene checks failed: os-metadata, gitignore, empty-files, history-blobs"),
+    error_code(32, "project configuration checks failed: manifest, assets-structure, resources, submodules, asmdef-references"),
+    error_code(64, "commit message checks failed: commit-message"),
+    error_code(128, "warnings were reported, with --fail-on-warnings")
+)]
+struct CommandRoot {
+    #[argh(positional)]
+    path: Vec<String>,
+
+    #[argh(option, description = "commit")]
+    commit: Option<String>,
+
+    #[argh(
+        option,
+        description = "check the tree with the given oid instead of a commit, e.g. one written by another tool"
+    )]
+    tree: Option<String>,
+
+    #[argh(
+        option,
+        description = "report only findings absent at the merge base of the commit and the given ref, e.g. `origin/main`, as regressions"
+    )]
+    check_regression_from: Option<String>,
+
+    #[argh(
+        option,
+        description = "regex every checked commit message must match, e.g. `\\[LFS\\]`, failing with exit code 64"
+    )]
+    commit_message_pattern: Option<regex::Regex>,
+
+    #[argh(
+        switch,
+        description = "do not report os metadata files (.DS_Store, Thumbs.db, ...)"
+    )]
+    allow_os_files: bool,
+
+    #[argh(
+        option,
+        description = "check each commit since the given tag, reporting only paths it changed"
+    )]
+    since_tag: Option<String>,
+
+    #[argh(
+        option,
+        description = "with --since-tag or --stdin, check only commits whose author email contains the given text"
+    )]
+    author: Option<String>,
+
+    #[argh(
+        switch,
+        description = "match --author against the whole email instead of a part of it"
+    )]
+    author_exact: bool,
+
+    #[argh(
+        switch,
+        description = "check only the paths the commit changed from its first parent, skipping unchanged subtrees"
+    )]
+    incremental: bool,
+
+    #[argh(
+        option,
+        description = "check only the paths changed between the given base commit and the commit, e.g. the target branch of a pull request"
+    )]
+    since: Option<String>,
+
+    #[argh(
+        option,
+        description = "check the commit of every tag matching the glob, e.g. `v*`, like check-all-tags"
+    )]
+    tags: Option<String>,
+
+    #[argh(
+        switch,
+        description = "report findings but always exit 0, e.g. to collect metrics during a grace period"
+    )]
+    no_fail: bool,
+
+    #[argh(
+        switch,
+        description = "compare lfs pointer sizes with the objects in .git/lfs/objects, or lfs.storage"
+    )]
+    verify_lfs_size: bool,
+
+    #[argh(
+        switch,
+        description = "warn about lfs pointers whose object isn't in .git/lfs/objects, or lfs.storage, e.g. to diagnose smudge failures"
+    )]
+    verify_lfs_reachability: bool,
+
+    #[argh(
+        switch,
+        description = "skip checks that need the lfs objects on disk, e.g. on partial clones; pointers are still validated and --verify-lfs-size and --verify-lfs-reachability are ignored"
+    )]
+    no_lfs_object_check: bool,
+
+    #[argh(
+        switch,
+        description = "skip paths ignored by the .gitignore files of the checked tree in the meta check, as Unity doesn't import them"
+    )]
+    respect_gitignore: bool,
+
+    #[argh(
+        switch,
+        description = "run the checks one after another on the main thread, including the parallel lfs blob reads, so the log is ordered deterministically, e.g. for debugging"
+    )]
+    no_threads: bool,
+
+    #[argh(
+        switch,
+        description = "read `<old> <new> <ref>` lines from stdin, as in a pre-receive hook, and check each new commit"
+    )]
+    stdin: bool,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_OUTPUT_FORMAT\", OutputFormat::Text)",
+        description = "format of the findings written to stdout or --output-file: text (default), csv, jsonl (streamed to stdout as findings are found), gitlab (code quality report)"
+    )]
+    output_format: OutputFormat,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_REPORT_FORMAT\", ReportFormat::Short)",
+        description = "how findings are shown: path-only, short (default, check, path and message), long (short plus a suggested fix, also added to csv and jsonl)"
+    )]
+    report_format: ReportFormat,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_COLOR\", ColorChoice::Auto)",
+        description = "color the log: auto (default, when stderr is a terminal), always, never; the log is never colored with an --output-format other than text"
+    )]
+    color: ColorChoice,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_PATH_STYLE\", PathStyle::Repo)",
+        description = "paths of findings relative to the repository (repo, default), to the working directory (cwd) or absolute"
+    )]
+    path_style: PathStyle,
+
+    #[argh(option, description = "write findings to the given file")]
+    output_file: Option<PathBuf>,
+
+    #[argh(
+        option,
+        description = "write the final summary to the given file instead of the log, as json with --output-format jsonl"
+    )]
+    summary_file: Option<PathBuf>,
+
+    #[argh(
+        switch,
+        description = "warn about .meta files whose importer doesn't match the asset extension"
+    )]
+    check_importer: bool,
+
+    #[argh(
+        option,
+        description = "expected importer for an extension, e.g. `png=TextureImporter`, repeatable"
+    )]
+    importer: Vec<String>,
+
+    #[argh(
+        option,
+        description = "log at most N findings per check; counts and output files stay complete"
+    )]
+    max_findings: Option<usize>,
+
+    #[argh(
+        option,
+        description = "clone the repository into a temporary directory and check it; https and ssh urls need the `remote` feature, credentials are read from CHECKLFS_GIT_USERNAME/CHECKLFS_GIT_PASSWORD or the ssh agent"
+    )]
+    repo_url: Option<String>,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_DEPTH\", 1)",
+        description = "history depth for --repo-url, 0 for full history; increase it for --since-tag"
+    )]
+    depth: i32,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_CASE_SCOPE\", CaseScope::Global)",
+        description = "compare case-insensitive names within each directory (dir) or across the whole tree (global, default)"
+    )]
+    case_scope: CaseScope,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_ATTR_SOURCE\", AttrSource::Index)",
+        description = "where the lfs, per-type lfs, shader and binary checks read .gitattributes from: index (default), workdir (then the index), head (and the index) or tree (those committed with the checked tree, the right source for --since-tag and other historical checks)"
+    )]
+    attr_source: AttrSource,
+
+    #[argh(
+        switch,
+        description = "print how long each check took, summed over every checked commit"
+    )]
+    timing: bool,
+
+    #[argh(
+        option,
+        description = "run only the named check, repeatable; `default` selects the checks run by default"
+    )]
+    check: Vec<String>,
+
+    #[argh(
+        switch,
+        description = "print every check with its description, whether it runs by default and its exit code bit, as jsonl with --output-format jsonl, and exit"
+    )]
+    list_checks: bool,
+
+    #[argh(
+        switch,
+        description = "keep running and check again whenever files of the working directory that aren't ignored change, once they were quiet for 300ms; the checks read the committed tree, or the staged one with --staged, and no exit code applies"
+    )]
+    watch: bool,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_DUPLICATE_BASENAME_MIN\", DUPLICATE_BASENAME_MIN)",
+        description = "minimum number of files sharing a basename for duplicate-basenames"
+    )]
+    duplicate_basename_min: usize,
+
+    #[argh(
+        switch,
+        description = "report meta guids used for different assets on different local branches"
+    )]
+    all_branches: bool,
+
+    #[argh(
+        option,
+        description = "check only the given directory of the tree, e.g. `Assets/Characters`; paths stay relative to the repository root"
+    )]
+    subtree: Option<PathBuf>,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_LFS_THRESHOLD\", LFS_THRESHOLD)",
+        description = "size in bytes from which a file tracked by lfs must be a pointer, 150 by default"
+    )]
+    lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_TEXTURE_LFS_THRESHOLD\", TEXTURE_LFS_THRESHOLD)",
+        description = "size in bytes above which the texture check requires lfs, 512 KiB by default"
+    )]
+    texture_lfs_threshold: usize,
+
+    #[argh(
+        switch,
+        description = "check the files staged in the index instead of a commit, for pre-commit hooks"
+    )]
+    staged: bool,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_AUDIO_LFS_THRESHOLD\", AUDIO_LFS_THRESHOLD)",
+        description = "size in bytes above which the audio check requires lfs, 0 (every audio file) by default"
+    )]
+    audio_lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_VIDEO_LFS_THRESHOLD\", VIDEO_LFS_THRESHOLD)",
+        description = "size in bytes above which the video check requires lfs, 0 (every video file) by default"
+    )]
+    video_lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_SCENE_LFS_THRESHOLD\", SCENE_LFS_THRESHOLD)",
+        description = "size in bytes above which the scene check requires lfs for scenes and prefabs, 5 MiB by default"
+    )]
+    scene_lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_SHADER_LFS_THRESHOLD\", SHADER_LFS_THRESHOLD)",
+        description = "size in bytes above which the shader check suggests lfs, 1 MiB by default"
+    )]
+    shader_lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_RESOURCES_THRESHOLD\", RESOURCES_THRESHOLD)",
+        description = "size in bytes above which the resources check reports assets in Resources folders, 1 MiB by default"
+    )]
+    resources_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_STREAMING_ASSETS_LFS_THRESHOLD\", STREAMING_ASSETS_LFS_THRESHOLD)",
+        description = "size in bytes above which the streaming-assets check requires lfs, 1 MiB by default"
+    )]
+    streaming_assets_lfs_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_HISTORY_BLOB_THRESHOLD\", HISTORY_BLOB_THRESHOLD)",
+        description = "size in bytes above which the history-blobs check reports blobs, 10 MiB by default"
+    )]
+    history_blob_threshold: usize,
+
+    #[argh(
+        option,
+        default = "env_default(\"CHECKLFS_MAX_COMPONENT_LENGTH\", MAX_COMPONENT_LENGTH)",
+        description = "length in bytes above which the component-length check reports file and directory names, 255 by default"
+    )]
+    max_component_length: usize,
+
+    #[argh(
+        switch,
+        description = "walk every commit reachable from a ref for the checks that need the whole history, i.e. history-blobs"
+    )]
+    deep: bool,
+
+    #[argh(
+        option,
+        description = "override the severity of a check's findings, e.g. `meta=warning,case=error`; only errors fail the run"
+    )]
+    severity: Vec<String>,
+
+    #[argh(
+        switch,
+        description = "fail the run when any warning is reported, like -Dwarnings, e.g. once checks lowered with --severity are clean"
+    )]
+    fail_on_warnings: bool,
+
+    #[argh(
+        switch,
+        short = 'v',
+        description = "log more details, -v for debug and -vv for every path visited by each check and the decision made"
+    )]
+    verbose: u8,
+
+    #[argh(
+        switch,
+        short = 'q',
+        description = "only log warnings, errors and the summary line"
+    )]
+    quiet: bool,
+
+    #[argh(
+        option,
+        description = "directory in which every entry needs a meta, repeatable; `Assets` by default, `.` for the whole tree"
+    )]
+    asset_root: Vec<String>,
+
+    #[argh(
+        option,
+        description = "gitignore-style glob of paths neither walked nor reported by any check, e.g. `ThirdParty/`, repeatable"
+    )]
+    exclude: Vec<String>,
+
+    #[argh(
+        switch,
+        description = "report files locked on the lfs server by other users, like `--check lfs-locks`; needs the `locks` feature, credentials are read from CHECKLFS_GIT_USERNAME/CHECKLFS_GIT_PASSWORD"
+    )]
+    check_lfs_locks: bool,
+
+    #[argh(
+        option,
+        description = "send a span per check with its error count and duration to the given OTLP/HTTP collector, e.g. http://localhost:4318; needs the `otel` feature"
+    )]
+    otel_endpoint: Option<String>,
+
+    #[argh(
+        option,
+        description = "extension reported by the lfs-text check when committed as an lfs pointer, repeatable; replaces the defaults cs, json, xml, txt, asmdef"
+    )]
+    lfs_text_extension: Vec<String>,
+
+    #[argh(subcommand)]
+    command: Option<Subcommand>,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+enum Subcommand {
+    CheckAllTags(CheckAllTags),
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(
+    subcommand,
+    name = "check-all-tags",
+    description = "check the commit of every tag and print the error counts per tag"
+)]
+struct CheckAllTags {
+    #[argh(option, description = "only check tags matching the glob, e.g. `v1.*`")]
+    tag_pattern: Option<String>,
+}
+
+/// Parses the environment variable `var`, `None` if it is unset or empty.
+fn env_flag<T: std::str::FromStr>(var: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(var) {
+        Ok(value) if !value.is_empty() => match value.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => anyhow::bail!("invalid value for {}: {}", var, e),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Returns the flag parsed by argh if it was given, else the value of the environment variable
+/// `var`, else `default`.
+fn resolve_flag<T: std::str::FromStr>(
+    flag: Option<T>,
+    var: &str,
+    default: Option<T>,
+) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match flag {
+        Some(flag) => Ok(Some(flag)),
+        None => Ok(env_flag(var)?.or(default)),
+    }
+}
+
+/// Default of an argh option, which argh only evaluates when the option is not given. Exits like
+/// argh does for invalid command line values since parsing can't return an error.
+fn env_default<T: std::str::FromStr>(var: &str, default: T) -> T
+where
+    T::Err: std::fmt::Display,
+{
+    match resolve_flag(None, var, Some(default)) {
+        Ok(value) => value.unwrap(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     }
+}
 
+/// Switches can only be turned on from the command line, so the environment turns them on too.
+fn resolve_switch(flag: &mut bool, var: &str) -> Result<()> {
+    let value = match std::env::var(var) {
+        Ok(value) => value.to_lowercase(),
+        Err(_) => return Ok(()),
+    };
+    match value.as_str() {
+        "1" | "true" | "yes" | "on" => *flag = true,
+        "" | "0" | "false" | "no" | "off" => {}
+        _ => anyhow::bail!("invalid value for {}: {}", var, value),
+    }
     Ok(())
 }
 
-fn test_case<P: AsRef<Path>>(repo_root: P, commit_id: &str) -> Result<usize> {
-    info!("checking case-insensitive-duplicated files");
+/// Repeatable flags fall back to a list in the environment when none were given.
+fn resolve_list(flag: &mut Vec<String>, var: &str) {
+    if !flag.is_empty() {
+        return;
+    }
+    if let Ok(value) = std::env::var(var) {
+        flag.extend(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned),
+        );
+    }
+}
 
-    let repo = git2::Repository::open(repo_root)?;
-    let commit_id = git2::Oid::from_str(commit_id)?;
-    let tree = repo.find_tree(commit_id)?;
-    let root = PathBuf::new();
+/// Fills flags that were not given on the command line from CHECKLFS_* environment variables.
+/// Defaulted options are resolved by argh through [`env_default`].
+fn resolve_env(arg: &mut CommandRoot) -> Result<()> {
+    if arg.path.is_empty() {
+        if let Some(paths) = std::env::var_os("CHECKLFS_PATH") {
+            arg.path.extend(
+                std::env::split_paths(&paths)
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .map(|p| p.to_string_lossy().into_owned()),
+            );
+        }
+    }
 
-    let mut name_set = HashSet::new();
-    iter_tree_case(&repo, &root.as_path(), &tree, &mut name_set)
+    arg.commit = resolve_flag(arg.commit.take(), "CHECKLFS_COMMIT", None)?;
+    arg.tree = resolve_flag(arg.tree.take(), "CHECKLFS_TREE", None)?;
+    arg.check_regression_from = resolve_flag(
+        arg.check_regression_from.take(),
+        "CHECKLFS_CHECK_REGRESSION_FROM",
+        None,
+    )?;
+    arg.since_tag = resolve_flag(arg.since_tag.take(), "CHECKLFS_SINCE_TAG", None)?;
+    arg.since = resolve_flag(arg.since.take(), "CHECKLFS_SINCE", None)?;
+    arg.author = resolve_flag(arg.author.take(), "CHECKLFS_AUTHOR", None)?;
+    arg.commit_message_pattern = resolve_flag(
+        arg.commit_message_pattern.take(),
+        "CHECKLFS_COMMIT_MESSAGE_PATTERN",
+        None,
+    )?;
+    arg.tags = resolve_flag(arg.tags.take(), "CHECKLFS_TAGS", None)?;
+    arg.output_file = resolve_flag(arg.output_file.take(), "CHECKLFS_OUTPUT_FILE", None)?;
+    arg.summary_file = resolve_flag(arg.summary_file.take(), "CHECKLFS_SUMMARY_FILE", None)?;
+    arg.max_findings = resolve_flag(arg.max_findings.take(), "CHECKLFS_MAX_FINDINGS", None)?;
+    arg.repo_url = resolve_flag(arg.repo_url.take(), "CHECKLFS_REPO_URL", None)?;
+    arg.subtree = resolve_flag(arg.subtree.take(), "CHECKLFS_SUBTREE", None)?;
+
+    resolve_switch(&mut arg.allow_os_files, "CHECKLFS_ALLOW_OS_FILES")?;
+    resolve_switch(&mut arg.no_fail, "CHECKLFS_NO_FAIL")?;
+    resolve_switch(&mut arg.fail_on_warnings, "CHECKLFS_FAIL_ON_WARNINGS")?;
+    resolve_switch(&mut arg.verify_lfs_size, "CHECKLFS_VERIFY_LFS_SIZE")?;
+    resolve_switch(
+        &mut arg.verify_lfs_reachability,
+        "CHECKLFS_VERIFY_LFS_REACHABILITY",
+    )?;
+    resolve_switch(&mut arg.no_lfs_object_check, "CHECKLFS_NO_LFS_OBJECT_CHECK")?;
+    resolve_switch(&mut arg.respect_gitignore, "CHECKLFS_RESPECT_GITIGNORE")?;
+    resolve_switch(&mut arg.no_threads, "CHECKLFS_NO_THREADS")?;
+    resolve_switch(&mut arg.stdin, "CHECKLFS_STDIN")?;
+    resolve_switch(&mut arg.staged, "CHECKLFS_STAGED")?;
+    resolve_switch(&mut arg.incremental, "CHECKLFS_INCREMENTAL")?;
+    resolve_switch(&mut arg.deep, "CHECKLFS_DEEP")?;
+    resolve_switch(&mut arg.author_exact, "CHECKLFS_AUTHOR_EXACT")?;
+    resolve_switch(&mut arg.check_importer, "CHECKLFS_CHECK_IMPORTER")?;
+    resolve_switch(&mut arg.timing, "CHECKLFS_TIMING")?;
+    resolve_switch(&mut arg.list_checks, "CHECKLFS_LIST_CHECKS")?;
+    resolve_switch(&mut arg.watch, "CHECKLFS_WATCH")?;
+    resolve_switch(&mut arg.quiet, "CHECKLFS_QUIET")?;
+    // -v is counted, so the environment holds the count, e.g. CHECKLFS_VERBOSE=2 for -vv
+    if arg.verbose == 0 {
+        arg.verbose = env_flag("CHECKLFS_VERBOSE")?.unwrap_or(0);
+    }
+    resolve_switch(&mut arg.all_branches, "CHECKLFS_ALL_BRANCHES")?;
+    resolve_switch(&mut arg.check_lfs_locks, "CHECKLFS_CHECK_LFS_LOCKS")?;
+    arg.otel_endpoint = resolve_flag(arg.otel_endpoint.take(), "CHECKLFS_OTEL_ENDPOINT", None)?;
+
+    resolve_list(&mut arg.importer, "CHECKLFS_IMPORTER");
+    resolve_list(&mut arg.check, "CHECKLFS_CHECK");
+    resolve_list(&mut arg.severity, "CHECKLFS_SEVERITY");
+    resolve_list(&mut arg.lfs_text_extension, "CHECKLFS_LFS_TEXT_EXTENSION");
+    resolve_list(&mut arg.asset_root, "CHECKLFS_ASSET_ROOT");
+    resolve_list(&mut arg.exclude, "CHECKLFS_EXCLUDE");
+    Ok(())
 }
 
-fn iter_tree_case(
-    repo: &Repository,
-    prefix: &Path,
-    tree: &Tree,
-    names: &mut HashSet<String>,
-) -> Result<usize> {
-    let mut count = 0;
-
-    for entry in tree.iter() {
-        let name = match entry.name() {
-            None => continue,
-            Some(name) => name,
+/// Logs findings, printing at most `max_findings` per check followed by a truncation notice.
+fn log_findings(findings: &[Finding], max_findings: Option<usize>, format: ReportFormat) {
+    let max_findings = max_findings.unwrap_or(usize::MAX);
+    for &check in Check::ALL {
+        let mut findings = findings.iter().filter(|f| f.check == check);
+        for finding in findings.by_ref().take(max_findings) {
+            finding.log(format);
+        }
+        let rest = findings.count();
+        if rest > 0 {
+            warn!("[{}] (... and {} more)", check.name(), rest);
+        }
+    }
+}
+
+/// Checks selected by `--check`, or the default set, adjusted by the older per-check switches.
+fn enabled_checks(arg: &CommandRoot) -> Result<HashSet<Check>> {
+    let defaults = Check::ALL.iter().copied().filter(|c| c.default_enabled());
+
+    let mut checks: HashSet<Check> = HashSet::new();
+    if arg.check.is_empty() {
+        checks.extend(defaults);
+    } else {
+        for name in &arg.check {
+            match name.as_str() {
+                "default" => checks.extend(defaults.clone()),
+                name => match Check::from_name(name) {
+                    Some(check) => {
+                        checks.insert(check);
+                    }
+                    None => anyhow::bail!("unknown check: {}", name),
+                },
+            }
+        }
+    }
+
+    if arg.allow_os_files {
+        checks.remove(&Check::OsMetadata);
+    }
+    if arg.check_importer {
+        checks.insert(Check::Importer);
+    }
+    if arg.all_branches {
+        checks.insert(Check::Guid);
+    }
+    if arg.check_lfs_locks {
+        checks.insert(Check::LfsLocks);
+    }
+    if arg.commit_message_pattern.is_some() {
+        checks.insert(Check::CommitMessage);
+    } else if checks.contains(&Check::CommitMessage) {
+        anyhow::bail!("the commit-message check needs --commit-message-pattern");
+    }
+    Ok(checks)
+}
+
+/// Severity overrides from `--severity check=severity` pairs.
+fn severity_map(arg: &CommandRoot) -> Result<HashMap<Check, Severity>> {
+    let mut severities = HashMap::new();
+    for item in arg.severity.iter().flat_map(|item| item.split(',')) {
+        let (name, severity) = match item.split_once('=') {
+            Some(pair) => pair,
+            None => anyhow::bail!("invalid severity {:?}, expected check=severity", item),
+        };
+        let check = match Check::from_name(name.trim()) {
+            Some(check) => check,
+            None => anyhow::bail!("unknown check: {}", name),
         };
-        let obj = entry.to_object(repo)?;
-        let name = prefix.join(&name);
+        let severity = severity.trim().parse().map_err(anyhow::Error::msg)?;
+        severities.insert(check, severity);
+    }
+    Ok(severities)
+}
+
+/// Check settings from the command line.
+fn check_config(arg: &CommandRoot) -> Result<CheckConfig> {
+    let mut config = CheckConfig {
+        asset_roots: if arg.asset_root.is_empty() {
+            AssetRoots::default()
+        } else {
+            AssetRoots::new(&arg.asset_root)
+        },
+        exclude: arg.exclude.clone(),
+        meta_ignore: Arc::new(test_meta_ignore),
+        respect_gitignore: arg.respect_gitignore,
+        sequential: arg.no_threads,
+        checks: enabled_checks(arg)?,
+        subtree: arg.subtree.clone(),
+        case_scope: arg.case_scope,
+        attr_source: arg.attr_source,
+        // objects of a partial clone are missing, so the escape hatch wins over the opt-in
+        verify_lfs_size: arg.verify_lfs_size && !arg.no_lfs_object_check,
+        verify_lfs_reachability: arg.verify_lfs_reachability && !arg.no_lfs_object_check,
+        importers: importer_map(&arg.importer)?,
+        duplicate_basename_min: arg.duplicate_basename_min,
+        lfs_threshold: arg.lfs_threshold,
+        texture_lfs_threshold: arg.texture_lfs_threshold,
+        audio_lfs_threshold: arg.audio_lfs_threshold,
+        video_lfs_threshold: arg.video_lfs_threshold,
+        scene_lfs_threshold: arg.scene_lfs_threshold,
+        shader_lfs_threshold: arg.shader_lfs_threshold,
+        resources_threshold: arg.resources_threshold,
+        streaming_assets_lfs_threshold: arg.streaming_assets_lfs_threshold,
+        history_blob_threshold: arg.history_blob_threshold,
+        max_component_length: arg.max_component_length,
+        severities: severity_map(arg)?,
+        object_dirs: if arg.stdin {
+            quarantine_dirs()?
+        } else {
+            Vec::new()
+        },
+        ..CheckConfig::default()
+    };
+    if !arg.lfs_text_extension.is_empty() {
+        config.lfs_text_extensions = arg.lfs_text_extension.clone();
+    }
+    Ok(config)
+}
+
+/// Object directories git points a hook at with `GIT_OBJECT_DIRECTORY` and
+/// `GIT_ALTERNATE_OBJECT_DIRECTORIES`. In pre-receive the pushed objects are still in a
+/// quarantine directory that isn't part of the repository yet.
+fn quarantine_dirs() -> Result<Vec<PathBuf>> {
+    let cwd = std::env::current_dir()?;
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("GIT_OBJECT_DIRECTORY") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(paths) = std::env::var_os("GIT_ALTERNATE_OBJECT_DIRECTORIES") {
+        dirs.extend(std::env::split_paths(&paths));
+    }
+    Ok(dirs
+        .into_iter()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| cwd.join(dir))
+        .filter(|dir| dir.is_dir())
+        .collect())
+}
 
-        let path_str = name.to_str().expect("non-utf8 filename");
-        let lower_path_str = path_str.to_lowercase();
+/// Collects findings as the checks emit them. With `--output-format jsonl` and no output file
+/// each finding is also written to stdout right away.
+struct Collector<'a> {
+    arg: &'a CommandRoot,
+    /// Repository name prepended to paths when several repositories are checked.
+    prefix: Option<&'a Path>,
+    findings: Vec<Finding>,
+    error: Option<std::io::Error>,
+}
 
-        if !names.insert(lower_path_str) {
-            error!("case-insensitive duplicated entry: {}", path_str);
-            count += 1;
+impl<'a> Collector<'a> {
+    fn new(arg: &'a CommandRoot, prefix: Option<&'a Path>) -> Self {
+        Collector {
+            arg,
+            prefix,
+            findings: Vec::new(),
+            error: None,
         }
+    }
 
-        if let Some(ObjectType::Tree) = obj.kind() {
-            let tree = obj.peel_to_tree()?;
-            count += iter_tree_case(repo, &name, &tree, names)?;
+    fn push(&mut self, mut finding: Finding) {
+        if let Some(prefix) = self.prefix {
+            finding.path = prefix.join(&finding.path);
+        }
+        if self.arg.output_format == OutputFormat::Jsonl
+            && self.arg.output_file.is_none()
+            && self.error.is_none()
+        {
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            if let Err(e) = writeln!(stdout, "{}", finding_json(&finding, self.arg.report_format)) {
+                self.error = Some(e);
+            }
         }
+        self.findings.push(finding);
     }
 
-    Ok(count)
+    /// The collected findings, or the first error writing to stdout.
+    fn finish(self) -> Result<Vec<Finding>> {
+        match self.error {
+            Some(e) => Err(e.into()),
+            None => Ok(self.findings),
+        }
+    }
 }
 
-fn test_lfs<P: AsRef<Path>>(repo_root: P, commit_id: &str) -> Result<usize> {
-    info!("checking invalid lfs files");
+/// Runs all checks against a tree, collecting the findings streamed by [`check_all_with`].
+fn check_tree(
+    arg: &CommandRoot,
+    repo: &Repository,
+    tree_id: Oid,
+    filter: Option<Arc<PathFilter>>,
+    prefix: Option<&Path>,
+) -> Result<Report> {
+    let config = check_config(arg)?;
+    let mut collector = Collector::new(arg, prefix);
+    let timings = check_all_with(&config, repo, tree_id, filter, |finding| {
+        collector.push(finding)
+    })?;
+    let mut findings = collector.finish()?;
+
+    // findings arrive in the order they were found, sort to keep the output stable across runs
+    findings.sort_by(|a, b| (a.check, &a.path).cmp(&(b.check, &b.path)));
 
-    let repo = git2::Repository::open(repo_root)?;
-    let commit_id = git2::Oid::from_str(commit_id)?;
-    let tree = repo.find_tree(commit_id)?;
-    let root = PathBuf::new();
+    log_findings(&findings, arg.max_findings, arg.report_format);
 
-    iter_tree_lfs(&repo, &root, &tree)
+    Ok(Report::from_tree(tree_id, findings, timings))
 }
 
-fn iter_tree_lfs(repo: &Repository, prefix: &Path, tree: &Tree) -> Result<usize> {
-    let mut count = 0;
+/// Checks the merge base of the commit and `from`, then the commit, and reports only the
+/// findings of the commit the merge base doesn't have, i.e. violations reintroduced by a branch
+/// after they were fixed, or newly added. Findings are matched by check and path.
+fn check_regression(
+    arg: &CommandRoot,
+    repo: &Repository,
+    commit: &Commit,
+    from: &str,
+    prefix: Option<&Path>,
+) -> Result<Report> {
+    let from = repo.revparse_single(from)?.peel_to_commit()?;
+    let base = match repo.merge_base(commit.id(), from.id()) {
+        Ok(base) => base,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            anyhow::bail!(
+                "commit={} and {} have no merge base",
+                commit.id(),
+                from.id()
+            )
+        }
+        Err(e) => return Err(e.into()),
+    };
+    info!("checking merge base={}", base);
+
+    let config = check_config(arg)?;
+    let mut known = HashSet::new();
+    let base_tree = repo.find_commit(base)?.tree_id();
+    check_all_with(&config, repo, base_tree, None, |finding| {
+        known.insert((finding.check, finding.path));
+    })?;
 
-    for entry in tree.iter() {
-        let name = match entry.name() {
-            None => continue,
-            Some(name) => name,
-        };
+    let mut collector = Collector::new(arg, prefix);
+    let tree_id = commit.tree_id();
+    let timings = check_all_with(&config, repo, tree_id, None, |mut finding| {
+        if !known.contains(&(finding.check, finding.path.clone())) {
+            finding.message = format!("regression since {}: {}", base, finding.message);
+            collector.push(finding);
+        }
+    })?;
+    let mut findings = collector.finish()?;
+    findings.sort_by(|a, b| (a.check, &a.path).cmp(&(b.check, &b.path)));
+    log_findings(&findings, arg.max_findings, arg.report_format);
 
-        let obj = entry.to_object(repo)?;
+    Ok(Report::from_tree(tree_id, findings, timings))
+}
 
-        match obj.kind() {
-            Some(ObjectType::Tree) => {
-                let tree = obj.peel_to_tree()?;
-                let prefix = prefix.join(&name);
-                count += iter_tree_lfs(repo, &prefix, &tree)?;
+/// Collects paths touched between the first parent and the commit. Deleted paths are included
+/// so that a meta left behind by a removed file is reported, and parent directories of added
+/// files are included so that a new directory without a meta is reported.
+fn changed_paths(repo: &Repository, commit: &Commit) -> Result<Option<PathFilter>> {
+    let parent = match commit.parents().next() {
+        Some(parent) => parent,
+        None => return Ok(None),
+    };
+    let diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+    Ok(Some(diff_paths(&diff)))
+}
+
+/// Collects paths that differ between `base`, any revision resolving to a commit, and the
+/// commit, so that only what a branch changed is reported.
+fn paths_since(repo: &Repository, commit: &Commit, base: &str) -> Result<PathFilter> {
+    let base = repo.revparse_single(base)?.peel_to_commit()?;
+    info!("checking paths changed since={}", base.id());
+    let diff = repo.diff_tree_to_tree(Some(&base.tree()?), Some(&commit.tree()?), None)?;
+    Ok(diff_paths(&diff))
+}
+
+/// Old and new paths of every delta, plus the parent directories of added files so that checks
+/// on directories, e.g. a new directory without meta, are reported too.
+fn diff_paths(diff: &Diff) -> PathFilter {
+    let mut paths = HashSet::new();
+    for delta in diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path() {
+                paths.insert(path.to_owned());
+            }
+        }
+        if delta.status() == Delta::Added {
+            if let Some(path) = delta.new_file().path() {
+                paths.extend(path.ancestors().skip(1).map(Path::to_owned));
             }
-            Some(ObjectType::Blob) => {
-                let blob = obj.peel_to_blob()?;
-                let size = blob.size();
+        }
+    }
+    PathFilter::paths(paths)
+}
 
-                let full_path = Path::join(prefix, name);
+/// Checks the staged content, as a pre-commit hook would, reporting only paths that differ from
+/// HEAD. The index is written as a tree so that the checks run on the staged blobs.
+fn check_staged(arg: &CommandRoot, repo: &Repository, prefix: Option<&Path>) -> Result<Report> {
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        anyhow::bail!("index has conflicts, resolve them before checking staged files");
+    }
+    let tree_id = index.write_tree()?;
 
-                let attr = repo.get_attr(&full_path, "merge", git2::AttrCheckFlags::INDEX_ONLY)?;
-                if attr != Some("lfs") {
-                    continue;
-                }
+    let filter = match repo.head() {
+        Ok(head) => {
+            let head_tree = head.peel_to_tree()?;
+            let diff = repo.diff_tree_to_index(Some(&head_tree), Some(&index), None)?;
+            Some(Arc::new(diff_paths(&diff)))
+        }
+        // nothing is committed yet, so everything is staged
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+    let mut report = check_tree(arg, repo, tree_id, filter.clone(), prefix)?;
+
+    if enabled_checks(arg)?.contains(&Check::StagedMeta) {
+        report
+            .findings
+            .extend(run_repository_check(arg, prefix, |config, emit| {
+                test_staged_metas(config, repo, &index, filter.as_deref(), emit)
+            })?);
+    }
+    Ok(report)
+}
+
+/// Reports the commit when its message doesn't match `--commit-message-pattern`. The path of the
+/// finding is the commit id.
+fn check_commit_message(
+    arg: &CommandRoot,
+    commit: &Commit,
+    prefix: Option<&Path>,
+) -> Result<Report> {
+    let pattern = match &arg.commit_message_pattern {
+        Some(pattern) => pattern,
+        None => return Ok(Report::default()),
+    };
+    let config = check_config(arg)?;
+    let mut collector = Collector::new(arg, prefix);
+    let message = String::from_utf8_lossy(commit.message_bytes());
+    if !pattern.is_match(&message) {
+        let mut finding = Finding::error(
+            Check::CommitMessage,
+            commit.id().to_string(),
+            format!(
+                "message {:?} doesn't match {:?}",
+                commit.summary().unwrap_or(""),
+                pattern.as_str()
+            ),
+        );
+        config.apply_severity(&mut finding);
+        collector.push(finding);
+    }
+    let findings = collector.finish()?;
+    log_findings(&findings, arg.max_findings, arg.report_format);
+    Ok(Report {
+        findings,
+        ..Default::default()
+    })
+}
+
+/// Whether the commit was authored by `--author`, emails are compared case-insensitively.
+fn author_matches(arg: &CommandRoot, commit: &Commit) -> bool {
+    let author = match &arg.author {
+        Some(author) => author.to_lowercase(),
+        None => return true,
+    };
+    let email = commit.author().email().unwrap_or("").to_lowercase();
+    if arg.author_exact {
+        email == author
+    } else {
+        email.contains(&author)
+    }
+}
 
-                // TODO: check content
-                if size < 150 {
-                    continue;
+/// Checks every commit reachable from `tip` but not from the given tag, limiting reports to the
+/// paths each commit changed.
+fn check_since_tag(
+    arg: &CommandRoot,
+    repo: &Repository,
+    tip: &Commit,
+    tag: &str,
+    prefix: Option<&Path>,
+) -> Result<Report> {
+    let tag_commit = repo
+        .find_reference(&format!("refs/tags/{}", tag))?
+        .peel_to_commit()?;
+
+    let mut walk = repo.revwalk()?;
+    walk.push(tip.id())?;
+    walk.hide(tag_commit.id())?;
+
+    let checks = enabled_checks(arg)?;
+    let mut report = Report::default();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let message = commit.summary().unwrap_or("").to_owned();
+        if !author_matches(arg, &commit) {
+            debug!("skipping commit={} by {}", commit.id(), commit.author());
+            continue;
+        }
+        info!("checking commit={}, message={:?}", commit.id(), message);
+
+        let filter = changed_paths(repo, &commit)?.map(Arc::new);
+        let mut commit_report = check_tree(arg, repo, commit.tree_id(), filter, prefix)?;
+        commit_report.add(check_commit_message(arg, &commit, prefix)?);
+        if commit_report.total() > 0 {
+            error!(
+                "violations in commit={}, message={:?}: {}",
+                commit.id(),
+                message,
+                commit_report.summary(&checks)
+            );
+        }
+        report.add(commit_report);
+    }
+    Ok(report)
+}
+
+/// Checks the commit of every tag matching the pattern, in tag name order, then prints a table
+/// of error counts to show which release introduced a violation.
+fn check_all_tags(
+    arg: &CommandRoot,
+    repo: &Repository,
+    pattern: Option<&str>,
+    prefix: Option<&Path>,
+) -> Result<Report> {
+    let checks = enabled_checks(arg)?;
+    let checks = Check::ALL
+        .iter()
+        .copied()
+        .filter(|check| checks.contains(check))
+        .collect::<Vec<_>>();
+
+    let mut report = Report::default();
+    let mut rows = Vec::new();
+    for tag in repo.tag_names(pattern)?.iter().flatten() {
+        let commit = repo
+            .find_reference(&format!("refs/tags/{}", tag))?
+            .peel_to_commit()?;
+        info!("checking tag={}, commit={}", tag, commit.id());
+
+        let mut tag_report = check_tree(arg, repo, commit.tree_id(), None, prefix)?;
+        let counts = checks
+            .iter()
+            .map(|&check| tag_report.error_count(check))
+            .collect::<Vec<_>>();
+        rows.push((tag.to_owned(), commit.id(), counts));
+
+        tag_report.commits.push(CommitInfo::new(&commit));
+        report.add(tag_report);
+    }
+
+    let header = checks
+        .iter()
+        .map(|check| format!("{:>8}", check.name()))
+        .collect::<Vec<_>>();
+    info!("{:<20} | {:<10} | {}", "tag", "commit", header.join(" | "));
+    for (tag, id, counts) in rows {
+        let counts = counts
+            .iter()
+            .zip(&header)
+            .map(|(count, name)| format!("{:>width$}", count, width = name.len()))
+            .collect::<Vec<_>>();
+        let id = id.to_string();
+        info!("{:<20} | {:<10} | {}", tag, &id[..10], counts.join(" | "));
+    }
+    Ok(report)
+}
+
+/// Checks the new tip of every ref update given on stdin as `<old> <new> <ref>` lines, the
+/// format git passes to pre-receive hooks, and the message of every pushed commit in
+/// `<old>..<new>`. Deleted refs and refs pointing at something other than a commit are skipped.
+fn check_stdin(arg: &CommandRoot, repo: &Repository, prefix: Option<&Path>) -> Result<Report> {
+    let mut report = Report::default();
+    let mut checked = HashSet::new();
+    let mut walked = HashSet::new();
+
+    for line in std::io::stdin().lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let (old, new, refname) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(old), Some(new), Some(refname)) => (old, new, refname),
+            _ => {
+                if !line.trim().is_empty() {
+                    warn!("ignoring malformed input line: {:?}", line);
                 }
-                error!("should be in LFS: {:?}, {}", full_path, size);
-                count += 1;
+                continue;
             }
-            _ => {
+        };
+
+        let (old, new) = (Oid::from_str(old)?, Oid::from_str(new)?);
+        if new.is_zero() {
+            warn!("skipping deleted ref={}", refname);
+            continue;
+        }
+        // an annotated tag points at the tag object, the tree is the one of its commit
+        let object = repo.find_object(new, None)?;
+        let commit = match object.peel_to_commit() {
+            Ok(commit) => commit,
+            Err(_) => {
+                warn!(
+                    "skipping ref={}, {} is a {}",
+                    refname,
+                    new,
+                    object.kind().map_or("unknown object", |kind| kind.str())
+                );
                 continue;
             }
+        };
+        if !checked.insert(commit.id()) {
+            continue;
+        }
+
+        info!("checking ref={}, commit={}", refname, commit.id());
+        report.add(check_tree(arg, repo, commit.tree_id(), None, prefix)?);
+        report.commits.push(CommitInfo::new(&commit));
+
+        let mut walk = repo.revwalk()?;
+        walk.push(commit.id())?;
+        match repo
+            .find_object(old, None)
+            .and_then(|old| old.peel_to_commit())
+        {
+            Ok(old) => walk.hide(old.id())?,
+            // a new ref brings every commit that no other ref has yet
+            _ => walk.hide_glob("refs/*")?,
+        }
+        for oid in walk {
+            let commit = repo.find_commit(oid?)?;
+            if !walked.insert(commit.id()) {
+                continue;
+            }
+            if !author_matches(arg, &commit) {
+                debug!("skipping commit={} by {}", commit.id(), commit.author());
+                continue;
+            }
+            report.add(check_commit_message(arg, &commit, prefix)?);
         }
     }
-    Ok(count)
+    Ok(report)
 }
 
-#[derive(FromArgs, Debug)]
-#[argh(description = "checklfs")]
-struct CommandRoot {
-    #[argh(positional)]
-    path: String,
+/// Clones `url` into a temporary directory, which is removed when the returned handle is
+/// dropped. A `depth` of 0 fetches the full history.
+fn clone_repo(url: &str, depth: i32) -> Result<tempfile::TempDir> {
+    let dir = tempfile::Builder::new().prefix("checklfs-").tempdir()?;
+    info!("cloning url={}, depth={}, dir={:?}", url, depth, dir.path());
 
-    #[argh(option, description = "commit")]
-    commit: Option<String>,
+    let mut callbacks = RemoteCallbacks::new();
+    // libgit2 asks again for as long as the credentials are rejected, and neither the agent nor
+    // the environment would give different ones
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        let is_http = url.starts_with("http://") || url.starts_with("https://");
+        if is_http || allowed.contains(CredentialType::SSH_KEY) {
+            attempts += 1;
+            if attempts > 1 {
+                return Err(git2::Error::from_str(&format!(
+                    "authentication to {} failed",
+                    url
+                )));
+            }
+        }
+        if is_http {
+            let username = std::env::var("CHECKLFS_GIT_USERNAME").unwrap_or_default();
+            let password = std::env::var("CHECKLFS_GIT_PASSWORD").unwrap_or_default();
+            Cred::userpass_plaintext(&username, &password)
+        } else if allowed.contains(CredentialType::SSH_KEY) {
+            Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else {
+            Cred::default()
+        }
+    });
+
+    let mut fetch = FetchOptions::new();
+    fetch.remote_callbacks(callbacks);
+    // libgit2's local transport can't do shallow fetches, scp-like `host:path` urls aren't local
+    let local = url.starts_with("file://") || Path::new(url).exists();
+    if depth > 0 && !local {
+        fetch.depth(depth);
+    }
+    build::RepoBuilder::new()
+        .fetch_options(fetch)
+        .clone(url, dir.path())?;
+    Ok(dir)
+}
+
+/// Prints the available checks to stdout, one JSON object per line for jsonl and a table
+/// otherwise.
+fn list_checks<W: std::io::Write>(mut w: W, format: OutputFormat) -> std::io::Result<()> {
+    if format != OutputFormat::Jsonl {
+        writeln!(
+            w,
+            "{:<20} {:<8} {:>4}  description",
+            "check", "enabled", "exit"
+        )?;
+    }
+    for &check in Check::ALL {
+        if format == OutputFormat::Jsonl {
+            let json = serde_json::json!({
+                "name": check.name(),
+                "description": check.description(),
+                "default": check.default_enabled(),
+                "exit_code": check.exit_code(),
+            });
+            writeln!(w, "{}", json)?;
+        } else {
+            let enabled = if check.default_enabled() {
+                "default"
+            } else {
+                "opt-in"
+            };
+            writeln!(
+                w,
+                "{:<20} {:<8} {:>4}  {}",
+                check.name(),
+                enabled,
+                check.exit_code(),
+                check.description()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// How long the working directory has to be quiet before `--watch` runs again, so that a bulk
+/// reimport triggers a single run.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Whether a change of `path` can change the results of a run in `repo`: a file of the working
+/// directory that isn't ignored, or the index, HEAD or a ref in the git directory. The files a
+/// run writes are in `outputs`, so that it doesn't trigger itself.
+fn watch_relevant(repo: &Repository, path: &Path, outputs: &[PathBuf]) -> bool {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    if outputs.contains(&path) {
+        return false;
+    }
+    let git_dir = std::fs::canonicalize(repo.path()).unwrap_or_else(|_| repo.path().to_owned());
+    if let Ok(relative) = path.strip_prefix(&git_dir) {
+        return relative == Path::new("index")
+            || relative == Path::new("HEAD")
+            || relative == Path::new("packed-refs")
+            || relative.starts_with("refs");
+    }
+    let workdir = match repo
+        .workdir()
+        .and_then(|dir| std::fs::canonicalize(dir).ok())
+    {
+        Some(workdir) => workdir,
+        None => return false,
+    };
+    match path.strip_prefix(&workdir) {
+        Ok(relative) => !repo.status_should_ignore(relative).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Paths of a change `watch` acts on, reading a file or a directory also produces an event.
+fn changed_paths_of(event: notify::Result<notify::Event>) -> Vec<PathBuf> {
+    match event {
+        Ok(event) if event.kind.is_access() => Vec::new(),
+        Ok(event) => event.paths,
+        Err(e) => {
+            warn!("watching for changes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Runs the checks, then again whenever files of the working directory change, once they were
+/// quiet for [`WATCH_DEBOUNCE`], until interrupted. Failed runs are logged instead of exiting.
+fn watch(arg: &CommandRoot) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
+
+    if arg.repo_url.is_some() || arg.stdin {
+        anyhow::bail!("--watch can't be combined with --repo-url or --stdin");
+    }
+    let repos = arg
+        .path
+        .iter()
+        .map(git2::Repository::open)
+        .collect::<Result<Vec<_>, _>>()?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for repo in &repos {
+        let dir = std::fs::canonicalize(repo.workdir().unwrap_or(repo.path()))?;
+        watcher.watch(&dir, RecursiveMode::Recursive)?;
+        // the git directory of a linked worktree or a submodule is elsewhere
+        let git_dir = std::fs::canonicalize(repo.path())?;
+        if !git_dir.starts_with(&dir) {
+            watcher.watch(&git_dir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    let interactive = std::io::IsTerminal::is_terminal(&std::io::stderr());
+    loop {
+        if interactive {
+            eprint!("\x1b[2J\x1b[H");
+        }
+        match run(arg) {
+            Ok(exit_code) => info!("exit code {}, watching for changes", exit_code),
+            Err(e) => error!("{:#}, watching for changes", e),
+        }
+
+        let outputs = arg
+            .output_file
+            .iter()
+            .chain(&arg.summary_file)
+            .filter_map(|path| std::fs::canonicalize(path).ok())
+            .collect::<Vec<_>>();
+        // wait for a change, then until the changes settle
+        let relevant = |path: &PathBuf| {
+            repos
+                .iter()
+                .any(|repo| watch_relevant(repo, path, &outputs))
+        };
+        let changed = loop {
+            if let Some(path) = changed_paths_of(receiver.recv()?)
+                .into_iter()
+                .find(relevant)
+            {
+                break path;
+            }
+        };
+        debug!(
+            "changed {}, waiting for the changes to settle",
+            changed.display()
+        );
+        let mut quiet_from = Instant::now() + WATCH_DEBOUNCE;
+        loop {
+            let timeout = quiet_from.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(timeout) {
+                Ok(event) => {
+                    if changed_paths_of(event).iter().any(relevant) {
+                        quiet_from = Instant::now() + WATCH_DEBOUNCE;
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Log target of the summary lines, which `--quiet` keeps.
+const SUMMARY_TARGET: &str = "checklfs::summary";
+
+/// Parses the command line like [`argh::from_env`], which doesn't accept clustered switches, so
+/// `-vv` is expanded to `-v -v` first. Arguments after `--` are left alone.
+fn parse_args() -> CommandRoot {
+    let strings = std::env::args_os()
+        .map(|arg| arg.into_string())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|arg| {
+            eprintln!("Invalid utf8: {}", arg.to_string_lossy());
+            std::process::exit(1)
+        });
+    let (program, strings) = match strings.split_first() {
+        Some(split) => split,
+        None => {
+            eprintln!("No program name, argv is empty");
+            std::process::exit(1)
+        }
+    };
+    let cmd = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program);
+
+    let mut args = Vec::new();
+    let mut rest = strings.iter();
+    for arg in rest.by_ref() {
+        if arg == "--" {
+            args.push(arg.as_str());
+            break;
+        }
+        match arg.strip_prefix('-') {
+            Some(switches) if switches.len() > 1 && switches.chars().all(|c| c == 'v') => {
+                args.extend(std::iter::repeat_n("-v", switches.len()))
+            }
+            _ => args.push(arg),
+        }
+    }
+    args.extend(rest.map(String::as_str));
+
+    CommandRoot::from_args(&[cmd], &args).unwrap_or_else(|early_exit| {
+        std::process::exit(match early_exit.status {
+            Ok(()) => {
+                println!("{}", early_exit.output);
+                0
+            }
+            Err(()) => {
+                eprintln!(
+                    "{}\nRun {} --help for more information.",
+                    early_exit.output, cmd
+                );
+                1
+            }
+        })
+    })
 }
 
 fn main() -> Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let mut arg = parse_args();
+    resolve_env(&mut arg)?;
 
-    let arg: CommandRoot = argh::from_env();
+    // RUST_LOG still takes precedence over -v and --quiet
+    let level = match arg.verbose {
+        0 if arg.quiet => format!("warn,{}=info", SUMMARY_TARGET),
+        0 => "info".to_owned(),
+        1 => "debug".to_owned(),
+        _ => "trace".to_owned(),
+    };
+    let mut logger =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level));
+    // the log of machine-readable runs is usually archived or parsed, keep escapes out of it
+    let color = match arg.output_format {
+        OutputFormat::Text => arg.color,
+        _ => ColorChoice::Never,
+    };
+    match color {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => {
+            logger.write_style(env_logger::WriteStyle::Always);
+        }
+        ColorChoice::Never => {
+            logger.write_style(env_logger::WriteStyle::Never);
+        }
+    }
+    logger.init();
+
+    if arg.list_checks {
+        list_checks(std::io::stdout().lock(), arg.output_format)?;
+        return Ok(());
+    }
+    if arg.watch {
+        return watch(&arg);
+    }
+
+    let exit_code = run(&arg)?;
+    if exit_code != 0 && !arg.no_fail {
+        std::process::exit(exit_code);
+    }
 
-    info!("repository={}", arg.path);
+    Ok(())
+}
 
+/// Runs the checks selected on the command line and returns the exit code. Any temporary clone
+/// is removed before returning.
+fn run(arg: &CommandRoot) -> Result<i32> {
     let start = Instant::now();
-    let repo = git2::Repository::open(&arg.path)?;
 
-    let commit = match arg.commit {
+    let clone_dir = match &arg.repo_url {
+        Some(url) => Some(clone_repo(url, arg.depth)?),
+        None => None,
+    };
+
+    // (display name, path) of every repository to check
+    let mut repos = Vec::new();
+    if let (Some(url), Some(dir)) = (&arg.repo_url, &clone_dir) {
+        repos.push((url.clone(), dir.path().to_owned()));
+    }
+    repos.extend(
+        arg.path
+            .iter()
+            .map(|path| (path.clone(), PathBuf::from(path))),
+    );
+
+    if repos.is_empty() {
+        anyhow::bail!("either a repository path or --repo-url is required");
+    }
+    if arg.stdin && repos.len() > 1 {
+        anyhow::bail!("--stdin checks a single repository");
+    }
+    if arg.tree.is_some()
+        && (arg.commit.is_some() || arg.since_tag.is_some() || arg.stdin || arg.staged)
+    {
+        anyhow::bail!("--tree can't be combined with --commit, --since-tag, --stdin or --staged");
+    }
+    if arg.since.is_some()
+        && (arg.since_tag.is_some()
+            || arg.incremental
+            || arg.check_regression_from.is_some()
+            || arg.tree.is_some()
+            || arg.stdin
+            || arg.staged)
+    {
+        anyhow::bail!(
+            "--since can't be combined with --since-tag, --incremental, --check-regression-from, --tree, --stdin or --staged"
+        );
+    }
+    if arg.check_regression_from.is_some() && (arg.since_tag.is_some() || arg.incremental) {
+        anyhow::bail!(
+            "--check-regression-from can't be combined with --since-tag or --incremental"
+        );
+    }
+    if arg.author.is_some() && arg.since_tag.is_none() && !arg.stdin {
+        warn!("--author only selects commits with --since-tag or --stdin");
+    }
+    if (arg.verify_lfs_size || arg.verify_lfs_reachability) && arg.no_lfs_object_check {
+        info!("--no-lfs-object-check is set, lfs objects aren't verified");
+    }
+
+    let mut report = Report::default();
+    for (name, path) in &repos {
+        let repo_start = Instant::now();
+        let repo_started_at = SystemTime::now();
+        info!("repository={}", name);
+        let prefix = path_prefix(arg.path_style, name, path, repos.len() > 1)?;
+        let repo_report = check_repository(arg, path, prefix.as_deref())?;
+
+        if let Some(endpoint) = &arg.otel_endpoint {
+            let commit = repo_report
+                .commits
+                .last()
+                .map_or(String::new(), |commit| commit.id.to_string());
+            // telemetry is best effort and doesn't fail the run
+            if let Err(e) = otel::export_spans(
+                endpoint,
+                name,
+                &commit,
+                repo_started_at,
+                repo_start.elapsed(),
+                &repo_report.spans,
+            ) {
+                warn!("can't export spans to {}: {:#}", endpoint, e);
+            }
+        }
+
+        if repos.len() > 1 {
+            info!(
+                target: SUMMARY_TARGET,
+                "repository={}, elapsed={:?}, {}",
+                name,
+                repo_start.elapsed(),
+                repo_report.summary(&enabled_checks(arg)?)
+            );
+        }
+        report.add(repo_report);
+    }
+
+    finish(arg, start, &report)
+}
+
+/// Checks a single repository, either the commit given on the command line or the commits read
+/// from stdin.
+fn check_repository(arg: &CommandRoot, path: &Path, prefix: Option<&Path>) -> Result<Report> {
+    let repo = open_repository(path, &check_config(arg)?.object_dirs)?;
+
+    if arg.stdin {
+        return check_stdin(arg, &repo, prefix);
+    }
+    if arg.staged {
+        return check_staged(arg, &repo, prefix);
+    }
+    if let Some(Subcommand::CheckAllTags(command)) = &arg.command {
+        return check_all_tags(arg, &repo, command.tag_pattern.as_deref(), prefix);
+    }
+    if let Some(pattern) = &arg.tags {
+        return check_all_tags(arg, &repo, Some(pattern), prefix);
+    }
+    if let Some(tree) = &arg.tree {
+        // a tree has no commit, so neither the commit message nor branches are checked
+        let object = repo.find_object(Oid::from_str(tree)?, None)?;
+        if object.kind() != Some(ObjectType::Tree) {
+            anyhow::bail!(
+                "{} is a {}, not a tree",
+                tree,
+                object.kind().map_or("unknown object", |kind| kind.str())
+            );
+        }
+        return check_tree(arg, &repo, object.id(), None, prefix);
+    }
+
+    let commit = match &arg.commit {
         Some(commit) => {
-            let oid = Oid::from_str(&commit)?;
-            let commit = repo.find_commit(oid)?;
-            commit
+            let oid = Oid::from_str(commit)?;
+            repo.find_commit(oid)?
         }
         None => {
             let head = repo.head()?.resolve()?.target().unwrap();
-            let commit = repo.find_commit(head)?;
-            commit
+            repo.find_commit(head)?
         }
     };
 
-    let commit_id = commit.tree_id().to_string();
-    let path = arg.path.to_string();
+    let mut report = match &arg.since_tag {
+        Some(tag) => check_since_tag(arg, &repo, &commit, tag, prefix)?,
+        None if arg.check_regression_from.is_some() => {
+            let from = arg.check_regression_from.as_deref().unwrap();
+            check_regression(arg, &repo, &commit, from, prefix)?
+        }
+        None if arg.since.is_some() => {
+            let filter = paths_since(&repo, &commit, arg.since.as_deref().unwrap())?;
+            check_tree(arg, &repo, commit.tree_id(), Some(Arc::new(filter)), prefix)?
+        }
+        None if arg.incremental => {
+            // a root commit has no parent to diff against and is checked in full
+            let filter = changed_paths(&repo, &commit)?.map(Arc::new);
+            check_tree(arg, &repo, commit.tree_id(), filter, prefix)?
+        }
+        None => check_tree(arg, &repo, commit.tree_id(), None, prefix)?,
+    };
+    if arg.since_tag.is_none() {
+        report.add(check_commit_message(arg, &commit, prefix)?);
+    }
 
-    let path0 = path.clone();
-    let commit_id0 = commit_id.clone();
-    let t_meta = std::thread::spawn(move || test_meta(&path0, &commit_id0));
+    let checks = enabled_checks(arg)?;
+    if checks.contains(&Check::Guid) {
+        let tips = branch_tips(&repo)?;
+        report
+            .findings
+            .extend(run_repository_check(arg, prefix, |config, emit| {
+                test_duplicate_guids_across_commits(config, &repo, &tips, emit)
+            })?);
+    }
 
-    let path0 = path.clone();
-    let commit_id0 = commit_id.clone();
-    let t_case = std::thread::spawn(move || test_case(&path0, &commit_id0));
+    if checks.contains(&Check::GuidStability) {
+        let base = match &arg.since {
+            Some(since) => Some(repo.revparse_single(since)?.peel_to_commit()?),
+            None => commit.parents().next(),
+        };
+        // a root commit has no previous guids
+        if let Some(base) = base {
+            let (old, new) = (base.tree()?, commit.tree()?);
+            report
+                .findings
+                .extend(run_repository_check(arg, prefix, |config, emit| {
+                    test_guid_stability(config, &repo, &old, &new, emit)
+                })?);
+        }
+    }
+
+    if checks.contains(&Check::MetaCommit) {
+        match &arg.since {
+            Some(since) => {
+                let base = repo.revparse_single(since)?.peel_to_commit()?;
+                report
+                    .findings
+                    .extend(run_repository_check(arg, prefix, |config, emit| {
+                        test_meta_commits(config, &repo, &base, &commit, emit)
+                    })?);
+            }
+            None => warn!("meta-commit checks the commits of a range and only runs with --since"),
+        }
+    }
 
-    let path0 = path.clone();
-    let commit_id0 = commit_id.clone();
-    let t_lfs = std::thread::spawn(move || test_lfs(&path0, &commit_id0));
+    if checks.contains(&Check::HistoryBlobs) {
+        if arg.deep {
+            report
+                .findings
+                .extend(run_repository_check(arg, prefix, |config, emit| {
+                    test_large_history_objects(config, &repo, emit)
+                })?);
+        } else {
+            warn!("history-blobs walks the whole history and only runs with --deep");
+        }
+    }
 
-    let meta_error_count = t_meta.join().unwrap()?;
-    let case_error_count = t_case.join().unwrap()?;
-    let lfs_error_count = t_lfs.join().unwrap()?;
+    report.commits.push(CommitInfo::new(&commit));
+    Ok(report)
+}
 
-    info!(
-        "elapsed={:?}, meta-errors={}, lfs-errors={}, case-errors={}",
-        start.elapsed(),
-        meta_error_count,
-        lfs_error_count,
-        case_error_count
-    );
+/// Runs a check of the repository rather than of a single tree, e.g. across branches or commits,
+/// and logs its findings like [`check_tree`] does.
+fn run_repository_check<F>(arg: &CommandRoot, prefix: Option<&Path>, f: F) -> Result<Vec<Finding>>
+where
+    F: FnOnce(&CheckConfig, &mut Emit) -> Result<(), CheckError>,
+{
+    let config = check_config(arg)?;
+    let mut collector = Collector::new(arg, prefix);
+    f(&config, &mut |mut finding| {
+        config.apply_severity(&mut finding);
+        collector.push(finding);
+    })?;
+    let findings = collector.finish()?;
+    log_findings(&findings, arg.max_findings, arg.report_format);
+    Ok(findings)
+}
 
-    let error_count = meta_error_count + case_error_count + lfs_error_count;
-    if error_count > 0 {
-        std::process::exit(1);
+/// The summary as a single-line JSON object, for `--summary-file` with `--output-format jsonl`.
+fn summary_json(arg: &CommandRoot, start: Instant, report: &Report) -> String {
+    let errors = Check::ALL
+        .iter()
+        .map(|&check| (check.name().to_owned(), report.error_count(check).into()))
+        .collect::<serde_json::Map<_, _>>();
+    let commits = report
+        .commits
+        .iter()
+        .map(|commit| commit.id.to_string())
+        .collect::<Vec<_>>();
+    let timings = report
+        .timings
+        .iter()
+        .map(|(check, elapsed)| (check.name().to_owned(), (elapsed.as_millis() as u64).into()))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::json!({
+        "url": arg.repo_url,
+        "elapsed_ms": start.elapsed().as_millis() as u64,
+        "exit_code": report.exit_code(arg.fail_on_warnings),
+        "errors": errors,
+        "commits": commits,
+        "elapsed_ms_by_check": timings,
+    })
+    .to_string()
+}
+
+/// Writes the findings, prints the summary and returns the bitmask of failed checks.
+fn finish(arg: &CommandRoot, start: Instant, report: &Report) -> Result<i32> {
+    match &arg.output_file {
+        Some(path) => {
+            let file = std::fs::File::create(path)?;
+            write_findings(
+                std::io::BufWriter::new(file),
+                arg.output_format,
+                arg.report_format,
+                &report.findings,
+            )?;
+        }
+        // text findings are already part of the log and jsonl findings were streamed
+        None if matches!(arg.output_format, OutputFormat::Text | OutputFormat::Jsonl) => {}
+        None => write_findings(
+            std::io::stdout().lock(),
+            arg.output_format,
+            arg.report_format,
+            &report.findings,
+        )?,
     }
 
-    Ok(())
+    for commit in &report.commits {
+        info!(
+            "commit={}, summary={:?}, author={}",
+            commit.id, commit.summary, commit.author
+        );
+    }
+    if arg.timing {
+        info!("{:<20} | {:>12} | errors", "check", "elapsed");
+        for (&check, elapsed) in &report.timings {
+            info!(
+                "{:<20} | {:>12.3?} | {}",
+                check.name(),
+                elapsed,
+                report.error_count(check)
+            );
+        }
+    }
+    let checks = enabled_checks(arg)?;
+    let summary = match &arg.repo_url {
+        Some(url) => format!(
+            "url={}, elapsed={:?}, {}",
+            url,
+            start.elapsed(),
+            report.summary(&checks)
+        ),
+        None => format!("elapsed={:?}, {}", start.elapsed(), report.summary(&checks)),
+    };
+    match &arg.summary_file {
+        Some(path) if arg.output_format == OutputFormat::Jsonl => {
+            std::fs::write(path, summary_json(arg, start, report) + "\n")?
+        }
+        Some(path) => std::fs::write(path, summary + "\n")?,
+        None => info!(target: SUMMARY_TARGET, "{}", summary),
+    }
+
+    Ok(report.exit_code(arg.fail_on_warnings))
 }
@@ -0,0 +1,203 @@
+//! Export of the check timings and error counts as OpenTelemetry spans, sent to an OTLP/HTTP
+//! endpoint in the JSON encoding, see
+//! https://opentelemetry.io/docs/specs/otlp/#otlphttp. A single POST per repository doesn't need
+//! the batching and async runtime of the `opentelemetry-otlp` SDK, so the request is built here.
+
+use anyhow::Result;
+use std::time::{Duration, SystemTime};
+
+/// A check run against a tree, exported as a span.
+#[derive(Debug, Clone)]
+pub struct CheckSpan {
+    pub name: String,
+    /// The checked tree, several trees are checked with e.g. `--since-tag`.
+    pub tree_id: String,
+    pub error_count: usize,
+    pub start: SystemTime,
+    pub duration: Duration,
+}
+
+/// Random id of `len` bytes in hex, as trace and span ids are.
+#[cfg(feature = "otel")]
+fn random_id(len: usize) -> Result<String> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow::anyhow!("no random ids: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(feature = "otel")]
+fn attribute(key: &str, value: serde_json::Value) -> serde_json::Value {
+    let value = match value {
+        serde_json::Value::Number(n) => serde_json::json!({ "intValue": n.to_string() }),
+        value => serde_json::json!({ "stringValue": value }),
+    };
+    serde_json::json!({ "key": key, "value": value })
+}
+
+#[cfg(feature = "otel")]
+fn unix_nanos(time: SystemTime) -> String {
+    let nanos = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    nanos.to_string()
+}
+
+/// Sends a `checklfs` span for the run of a repository started at `start`, with a child span for
+/// every check of every checked tree, from when the check started until it finished.
+/// `endpoint` is the base url of the collector, `/v1/traces` is appended unless already present.
+#[cfg(feature = "otel")]
+pub fn export_spans(
+    endpoint: &str,
+    repo_path: &str,
+    commit_sha: &str,
+    start: SystemTime,
+    elapsed: Duration,
+    checks: &[CheckSpan],
+) -> Result<()> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let url = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_owned()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    };
+
+    let trace_id = random_id(16)?;
+    let root_id = random_id(8)?;
+    let git_attributes = || {
+        vec![
+            attribute("git.commit_sha", commit_sha.into()),
+            attribute("git.repo_path", repo_path.into()),
+        ]
+    };
+
+    let mut spans = vec![serde_json::json!({
+        "traceId": trace_id,
+        "spanId": root_id,
+        "name": "checklfs",
+        "kind": 1,
+        "startTimeUnixNano": unix_nanos(start),
+        "endTimeUnixNano": unix_nanos(start + elapsed),
+        "attributes": git_attributes(),
+    })];
+    for check in checks {
+        let mut attributes = git_attributes();
+        attributes.push(attribute("check.name", check.name.as_str().into()));
+        attributes.push(attribute("git.tree_sha", check.tree_id.as_str().into()));
+        attributes.push(attribute("check.error_count", check.error_count.into()));
+        attributes.push(attribute(
+            "check.duration_ms",
+            (check.duration.as_millis() as u64).into(),
+        ));
+        spans.push(serde_json::json!({
+            "traceId": trace_id,
+            "spanId": random_id(8)?,
+            "parentSpanId": root_id,
+            "name": check.name,
+            "kind": 1,
+            "startTimeUnixNano": unix_nanos(check.start),
+            "endTimeUnixNano": unix_nanos(check.start + check.duration),
+            "attributes": attributes,
+        }));
+    }
+
+    let body = serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", "checklfs".into())],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "checklfs", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    });
+
+    let response = reqwest::blocking::Client::new()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("otlp export to {} failed: {}", url, status);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn export_spans(
+    _endpoint: &str,
+    _repo_path: &str,
+    _commit_sha: &str,
+    _start: SystemTime,
+    _elapsed: Duration,
+    _checks: &[CheckSpan],
+) -> Result<()> {
+    anyhow::bail!("--otel-endpoint needs checklfs built with the `otel` feature")
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::*;
+
+    fn span() -> CheckSpan {
+        CheckSpan {
+            name: "meta".to_owned(),
+            tree_id: "0123".to_owned(),
+            error_count: 3,
+            start: SystemTime::now(),
+            duration: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn export_spans_posts_a_child_span_per_check() {
+        let (url, server) = crate::test_http::serve(vec![(200, "{}".to_owned())]);
+        export_spans(
+            &url,
+            "/repo",
+            "abcd",
+            SystemTime::now(),
+            Duration::from_millis(10),
+            &[span()],
+        )
+        .unwrap();
+
+        let request = server.join().unwrap().remove(0);
+        let (request_line, body) = request.split_once('\n').unwrap();
+        assert!(
+            request_line.starts_with("POST /v1/traces "),
+            "{}",
+            request_line
+        );
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        let spans = &body["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["name"], "checklfs");
+        assert_eq!(spans[1]["name"], "meta");
+        assert_eq!(spans[1]["parentSpanId"], spans[0]["spanId"]);
+        let error_count = spans[1]["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|attribute| attribute["key"] == "check.error_count")
+            .unwrap();
+        assert_eq!(error_count["value"]["intValue"], "3");
+    }
+
+    #[test]
+    fn export_spans_fails_when_the_collector_rejects_them() {
+        let (url, server) = crate::test_http::serve(vec![(500, "{}".to_owned())]);
+        let error = export_spans(
+            &url,
+            "/repo",
+            "abcd",
+            SystemTime::now(),
+            Duration::from_millis(10),
+            &[span()],
+        )
+        .unwrap_err();
+        server.join().unwrap();
+        assert!(error.to_string().contains("failed: 500"), "{}", error);
+    }
+}
@@ -0,0 +1,397 @@
+use git2::{IndexAddOption, Repository};
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+#[test]
+fn unreadable_blob_fails_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(dir.path()).unwrap();
+    std::fs::write(
+        dir.path().join(".gitattributes"),
+        "*.png merge=lfs filter=lfs\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("Assets")).unwrap();
+    std::fs::write(dir.path().join("Assets/Broken.png"), [0; 200]).unwrap();
+    std::fs::write(
+        dir.path().join("Assets/Broken.png.meta"),
+        "fileFormatVersion: 2\n",
+    )
+    .unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+    index.write().unwrap();
+    let tree_id = index.write_tree().unwrap();
+    let id = repo
+        .find_tree(tree_id)
+        .unwrap()
+        .get_path("Assets/Broken.png".as_ref())
+        .unwrap()
+        .id()
+        .to_string();
+    std::fs::remove_file(
+        dir.path()
+            .join(".git/objects")
+            .join(&id[..2])
+            .join(&id[2..]),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_checklfs"))
+        .arg(dir.path())
+        .args(["--tree", &tree_id.to_string(), "--check", "lfs"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("can't read object"), "{}", stderr);
+    // the lfs group bit, not the internal error one
+    assert_eq!(output.status.code(), Some(8), "{}", stderr);
+}
+
+fn git(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// A bare remote whose pre-receive hook runs checklfs on the pushed refs, and a clone of it.
+fn remote_with_hook(dir: &Path) -> PathBuf {
+    let remote = dir.join("remote.git");
+    Repository::init_bare(&remote).unwrap();
+    let hook = remote.join("hooks/pre-receive");
+    std::fs::write(
+        &hook,
+        format!(
+            "#!/bin/sh\nexec {:?} --stdin --check meta --commit-message-pattern '^[A-Z]' .\n",
+            env!("CARGO_BIN_EXE_checklfs")
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let work = dir.join("work");
+    assert!(git(dir, &["clone", "-q", "remote.git", "work"])
+        .status
+        .success());
+    std::fs::create_dir(work.join("Assets")).unwrap();
+    work
+}
+
+#[test]
+fn pre_receive_checks_the_quarantined_push() {
+    let dir = tempfile::tempdir().unwrap();
+    let work = remote_with_hook(dir.path());
+
+    std::fs::write(work.join("Assets/Tree.png"), [0; 200]).unwrap();
+    assert!(git(&work, &["add", "-A"]).status.success());
+    assert!(git(&work, &["commit", "-qm", "Add a tree"])
+        .status
+        .success());
+    let output = git(&work, &["push", "-q", "origin", "HEAD:refs/heads/main"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert!(stderr.contains("missing meta file"), "{}", stderr);
+
+    std::fs::write(work.join("Assets/Tree.png.meta"), "fileFormatVersion: 2\n").unwrap();
+    assert!(git(&work, &["add", "-A"]).status.success());
+    assert!(git(&work, &["commit", "-qm", "Add its meta"])
+        .status
+        .success());
+    assert!(git(&work, &["tag", "-am", "Release", "v1"])
+        .status
+        .success());
+    let output = git(
+        &work,
+        &[
+            "push",
+            "-q",
+            "origin",
+            "HEAD:refs/heads/main",
+            "refs/tags/v1",
+        ],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn pre_receive_checks_every_pushed_commit_message() {
+    let dir = tempfile::tempdir().unwrap();
+    let work = remote_with_hook(dir.path());
+
+    std::fs::write(work.join("Assets/Tree.png"), [0; 200]).unwrap();
+    assert!(git(&work, &["add", "-A"]).status.success());
+    assert!(git(&work, &["commit", "-qm", "wip"]).status.success());
+    std::fs::write(work.join("Assets/Tree.png.meta"), "fileFormatVersion: 2\n").unwrap();
+    assert!(git(&work, &["add", "-A"]).status.success());
+    assert!(git(&work, &["commit", "-qm", "Add its meta"])
+        .status
+        .success());
+    let output = git(&work, &["push", "-q", "origin", "HEAD:refs/heads/main"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert!(stderr.contains("\"wip\""), "{}", stderr);
+}
+
+#[test]
+fn watch_runs_again_when_the_working_directory_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    Repository::init(dir.path()).unwrap();
+    std::fs::write(dir.path().join(".gitignore"), "Library/\n").unwrap();
+    std::fs::create_dir_all(dir.path().join("Library")).unwrap();
+    std::fs::create_dir_all(dir.path().join("Assets")).unwrap();
+    assert!(git(dir.path(), &["add", "-A"]).status.success());
+    assert!(git(dir.path(), &["commit", "-qm", "Init"]).status.success());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_checklfs"))
+        .arg("--watch")
+        .arg(dir.path())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            if line.unwrap().contains("watching for changes") && sender.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    let timeout = Duration::from_secs(30);
+    receiver.recv_timeout(timeout).expect("first run");
+
+    std::fs::write(dir.path().join("Library/cache"), "ignored").unwrap();
+    let ignored = receiver.recv_timeout(Duration::from_secs(2));
+    std::fs::write(dir.path().join("Assets/Tree.png"), [0; 200]).unwrap();
+    let changed = receiver.recv_timeout(timeout);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(ignored.is_err(), "an ignored file triggered a run");
+    changed.expect("no run after a change");
+}
+
+#[test]
+fn clustered_verbose_switches_enable_trace_logging() {
+    let dir = tempfile::tempdir().unwrap();
+    Repository::init(dir.path()).unwrap();
+    std::fs::create_dir(dir.path().join("Assets")).unwrap();
+    std::fs::write(dir.path().join("Assets/Tree.png"), [0; 200]).unwrap();
+    std::fs::write(
+        dir.path().join("Assets/Tree.png.meta"),
+        "fileFormatVersion: 2\n",
+    )
+    .unwrap();
+    assert!(git(dir.path(), &["add", "-A"]).status.success());
+    assert!(git(dir.path(), &["commit", "-qm", "Init"]).status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_checklfs"))
+        .args(["-vv", "--check", "meta"])
+        .arg(dir.path())
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("TRACE"), "{}", stderr);
+}
+
+#[test]
+fn verbosity_falls_back_to_the_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    let stderr = |verbose: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_checklfs"))
+            .args(["--check", "meta", "--no-fail"])
+            .arg(dir.path())
+            .env_remove("RUST_LOG")
+            .env("CHECKLFS_VERBOSE", verbose)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    assert!(stderr("2").contains("TRACE"));
+    assert!(!stderr("1").contains("TRACE"));
+    assert!(!stderr("0").contains("TRACE"));
+}
+
+/// A repository with a committed tree failing the meta and case checks.
+fn repo_with_findings(dir: &Path) {
+    git(dir, &["init", "-q"]);
+    std::fs::create_dir(dir.join("Assets")).unwrap();
+    for (path, contents) in [
+        ("Assets/a.txt", "a"),
+        ("Assets/a.txt.meta", "fileFormatVersion: 2\nguid: 11\n"),
+        ("Assets/A.txt", "A"),
+        ("Assets/NoMeta.txt", "no meta"),
+    ] {
+        std::fs::write(dir.join(path), contents).unwrap();
+    }
+    git(dir, &["add", "-A"]);
+    assert!(git(dir, &["commit", "-qm", "Init"]).status.success());
+}
+
+fn checklfs(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_checklfs"))
+        .arg(dir)
+        .args(args)
+        .env_remove("CHECKLFS_CHECK")
+        .output()
+        .unwrap()
+}
+
+/// Runs meta and case on `repo_with_findings`, writing the findings in `format`.
+fn output_findings(format: &str) -> (Option<i32>, String) {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    let output_file = dir.path().join("findings.out");
+    let output = checklfs(
+        dir.path(),
+        &[
+            "--check",
+            "meta",
+            "--check",
+            "case",
+            "--output-format",
+            format,
+            "--output-file",
+            output_file.to_str().unwrap(),
+        ],
+    );
+    (
+        output.status.code(),
+        std::fs::read_to_string(output_file).unwrap(),
+    )
+}
+
+#[test]
+fn text_output_matches_the_golden_file() {
+    let (code, output) = output_findings("text");
+    assert_eq!(code, Some(6));
+    assert_eq!(output, include_str!("golden/findings.txt"));
+}
+
+#[test]
+fn csv_output_matches_the_golden_file() {
+    let (code, output) = output_findings("csv");
+    assert_eq!(code, Some(6));
+    assert_eq!(output, include_str!("golden/findings.csv"));
+}
+
+#[test]
+fn jsonl_output_has_a_finding_per_line() {
+    let (_, output) = output_findings("jsonl");
+    let findings = output
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(findings.len(), 3);
+    assert_eq!(findings[0]["check"], "meta");
+    assert_eq!(findings[0]["path"], "Assets/A.txt");
+    assert_eq!(findings[2]["check"], "case");
+}
+
+#[test]
+fn gitlab_output_is_a_code_quality_report() {
+    let (_, output) = output_findings("gitlab");
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let issues = report.as_array().unwrap();
+    assert_eq!(issues.len(), 3);
+    assert_eq!(issues[0]["check_name"], "meta");
+    assert_eq!(issues[0]["location"]["path"], "Assets/A.txt");
+    assert_eq!(issues[0]["severity"], "major");
+    assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+}
+
+#[test]
+fn exit_code_combines_the_bits_of_the_failed_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    let exit_code = |args: &[&str]| checklfs(dir.path(), args).status.code();
+    assert_eq!(exit_code(&["--check", "meta"]), Some(2));
+    assert_eq!(exit_code(&["--check", "case"]), Some(4));
+    assert_eq!(exit_code(&["--check", "meta", "--check", "case"]), Some(6));
+    assert_eq!(exit_code(&["--check", "os-metadata"]), Some(0));
+}
+
+#[test]
+fn checks_fall_back_to_the_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    let exit_code = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_checklfs"))
+            .arg(dir.path())
+            .args(args)
+            .env("CHECKLFS_CHECK", "case")
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+    assert_eq!(exit_code(&[]), Some(4));
+    // the command line wins over the environment
+    assert_eq!(exit_code(&["--check", "meta"]), Some(2));
+}
+
+#[test]
+fn since_tag_checks_only_the_commits_after_the_tag() {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    git(dir.path(), &["tag", "v1"]);
+    std::fs::write(dir.path().join("Assets/New.txt"), "new").unwrap();
+    git(dir.path(), &["add", "-A"]);
+    assert!(git(dir.path(), &["commit", "-qm", "Add"]).status.success());
+
+    let output_file = dir.path().join("findings.csv");
+    let output = checklfs(
+        dir.path(),
+        &[
+            "--since-tag",
+            "v1",
+            "--check",
+            "meta",
+            "--output-format",
+            "csv",
+            "--output-file",
+            output_file.to_str().unwrap(),
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        std::fs::read_to_string(output_file).unwrap(),
+        "check,path,severity,message\nmeta,Assets/New.txt,error,missing meta file\n"
+    );
+}
+
+#[test]
+fn summary_lists_only_the_enabled_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    repo_with_findings(dir.path());
+    let summary_file = dir.path().join("summary.txt");
+    checklfs(
+        dir.path(),
+        &[
+            "--check",
+            "meta",
+            "--check",
+            "os-metadata",
+            "--summary-file",
+            summary_file.to_str().unwrap(),
+        ],
+    );
+    let summary = std::fs::read_to_string(summary_file).unwrap();
+    assert!(
+        summary.ends_with(", meta-errors=2, os-metadata-errors=0\n"),
+        "{}",
+        summary
+    );
+}